@@ -1,30 +1,20 @@
-use serde::{Deserialize, Serialize};
-use std::fs;
+use rust_project::config::ConfigLoader;
+use std::process::exit;
 
 const CONFIG_FILE: &str = "config.toml";
 fn main() {
-    let c = parse_config(CONFIG_FILE);
-    println!("{:#?}", c);
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct Config {
-    pub app: ApplicationConfig,
-    pub search: SearchConfig,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct SearchConfig {
-    pub address: String,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-pub struct ApplicationConfig {
-    pub port: i32,
-    pub page_size: i32,
-}
-
-pub fn parse_config(file_name: &str) -> Config {
-    let content = fs::read_to_string(file_name).expect("Failed to open TOML config file");
-    toml::from_str(&content).expect("Failed to parse TOML config file")
+    match ConfigLoader::new(CONFIG_FILE).load() {
+        Ok(loaded) => {
+            for warning in loaded.warnings() {
+                eprintln!("warning: {}", warning);
+            }
+            for entry in loaded.effective() {
+                println!("{}", entry);
+            }
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            exit(1);
+        }
+    }
 }
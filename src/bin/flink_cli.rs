@@ -0,0 +1,977 @@
+use regex::Regex;
+use rust_project::capacity::CapacityModel;
+use rust_project::checkpoint_trend::{self, CheckpointHistory, TrendConfig, TrendReport};
+use rust_project::client::flink::{ClusterConfig, FlinkClient, FlinkConfig};
+use rust_project::client::history::{self, HistoryClient, JobSource};
+use rust_project::config::{CURRENT_VERSION, migrate, schema, secret};
+use rust_project::config::{
+    ConfigError, ConfigLoader, ConfigWatcher, FeatureFlags, Format, LoadedConfig, Rollout,
+    SecretResolver,
+};
+use rust_project::graph::JobGraph;
+use rust_project::lint::{self, Severity};
+use rust_project::logs::{self, LogFollower, LogSource};
+use rust_project::models::flink::Flink;
+use rust_project::models::operator::OperatorResource;
+use rust_project::naming::{JobFilter, NamingConvention};
+use rust_project::operator;
+use rust_project::reconcile::{self, Manifest};
+use rust_project::sla::SlaStore;
+use rust_project::snapshot::{self, Snapshot};
+use rust_project::status_log::StatusLog;
+use rust_project::storm::{StormMonitor, StormState};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Once;
+use std::thread::sleep;
+use std::time::Duration;
+
+const CONFIG_FILE: &str = "config.toml";
+
+const USAGE: &str = "usage: flink_cli [--config config.toml] [--profile name] [--set key=value]... <command> [args]
+
+commands:
+  snapshot <cluster> <file> [--no-config]   save /jobs/overview and job configs to a file
+  diff <before> <after>                     compare two snapshot files
+  reconcile <cluster> <manifest> [--apply] [--allow-cancel]
+                                            plan (dry-run by default) and apply a job manifest
+  lint <manifest> [--cluster name]          check the job configuration of a manifest, against the
+                                            cluster's /jobmanager/config when a cluster is given
+  history <cluster> [jid] [--filter expr]   list archived jobs, or look up one job live then in history
+  graph <cluster> <jid> [--format dot|mermaid] [--no-backpressure]
+                                            render the job's operator DAG
+  logs <cluster> [jobmanager|<taskmanager-id>]
+                                            list log files of the JobManager and TaskManagers
+  tail <cluster> <jobmanager|taskmanager-id> <file> [-n 50] [-f] [--grep regex]
+                                            print the end of a log file, optionally following it
+  grep <cluster> <regex> [--file name] [--max-bytes n]
+                                            search the logs of all JobManager/TaskManager processes
+  sla poll <cluster> <store> [--interval 60] [--once]
+                                            record job availability into a local store file
+  sla report <store> [--until YYYY-MM-DD] [--format md|csv] [--filter expr] [--group-by field]
+                                            weekly availability, restarts and MTTR per job
+  status record <cluster> <log> [--interval 60] [--once]
+                                            append every polled job status to a local log file
+  status compact <log> [--retention-days 30]
+                                            drop unchanged records and records past retention
+  status query <log> <job name|jid> [--from time] [--to time]
+                                            print the job's states between two times (UTC)
+  capacity <cluster> [--fit parallelism] [--lose taskmanager-id] [--no-placement]
+                                            show slot usage and answer what-if capacity questions
+  checkpoints sample <cluster> <history> [--interval 60] [--once] [--alignment]
+                                            record the latest completed checkpoint of every running job
+  checkpoints report <history> [--format text|json] [--filter expr]
+                                            flag state growth, slow checkpoints and rising alignment
+  storm <cluster> [--interval 15] [--dry-run] [--once] [--state file]
+                                            detect restart storms and run the [flink.storm] remediation;
+                                            --state keeps restart counts and cooldowns between --once runs
+  config get <key> [--profile name] [--set key=value]...
+                                            print a merged [app]/[search] value and the layer it came from
+  config show [--profile name] [--set key=value]...
+                                            print the effective config with secrets redacted and the
+                                            origin of every value (default, file:line, env var, --set)
+  config features [--subject name] [--profile name] [--set key=value]...
+                                            show whether each [features] toggle is on for the profile
+                                            (and for the subject of a percentage rollout)
+  config check [--profile name] [--set key=value]...
+                                            load the merged config and report every invalid value
+  config watch [--interval 2] [--profile name] [--set key=value]...
+                                            print every change of the config files until interrupted
+  config init [file] [--force]              write a commented default config.toml (the --config file
+                                            by default)
+  config migrate <file>                     print the file upgraded to the current config version
+  config convert <input> [output] [--from toml|yaml|json] [--to toml|yaml|json]
+                                            translate a config file between formats (comments are
+                                            dropped), formats default to the file extensions
+  config schema [--format json|markdown]    print the JSON Schema or the Markdown reference of config.toml
+  config keygen <key-file>                  create a key file for encrypted enc: values
+  config encrypt [--key-file path] < value  print an enc: value for config.toml, reading the secret
+                                            from stdin (key from APP_KEY_FILE or
+                                            ~/.config/rust_project/secret.key by default)
+  k8s validate <manifest.yaml>              check FlinkDeployment/FlinkSessionJob resources
+  k8s jobs <manifest.yaml> [--filter expr]  list jobs from the status sections of a manifest dump
+  k8s generate <manifest.toml> --image <image> [--flink-version v1_17] [--namespace ns]
+                                            print FlinkDeployment YAML for the jobs of a manifest
+
+all config, including [flink], is merged from built-in defaults, /etc/rust_project/config.toml,
+~/.config/rust_project/config.toml, the --config file, its profile file (config.<profile>.toml,
+profile from --profile or APP_PROFILE), APP_ environment variables (APP_APP__PORT,
+APP_FLINK__NAMING__PATTERNS) and --set, for every command;
+values may reference secrets as ${env:NAME}, ${file:/path} or enc:...
+config files may be TOML, YAML (.yaml/.yml) or JSON, chosen by extension, or by
+--config-format toml|yaml|json for the --config file
+
+filters are space separated terms matched against the job name and the fields parsed by
+[flink.naming]: `team=orders env!=test etl`";
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let config_file = take_option(&mut args, "--config").unwrap_or(CONFIG_FILE.to_string());
+    let loader = match loader_for(&config_file, &mut args) {
+        Ok(loader) => loader,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(2);
+        }
+    };
+    if args.is_empty() {
+        eprintln!("{}", USAGE);
+        std::process::exit(2);
+    }
+    let command = args.remove(0);
+    let result = match command.as_str() {
+        "snapshot" => snapshot(&loader, args),
+        "diff" => diff(args),
+        "reconcile" => reconcile(&loader, args),
+        "lint" => lint(&loader, args),
+        "history" => history(&loader, args),
+        "graph" => graph(&loader, args),
+        "logs" => list_logs(&loader, args),
+        "tail" => tail(&loader, args),
+        "grep" => grep(&loader, args),
+        "sla" => sla(&loader, args),
+        "status" => status(&loader, args),
+        "capacity" => capacity(&loader, args),
+        "checkpoints" => checkpoints(&loader, args),
+        "storm" => storm(&loader, args),
+        "k8s" => k8s(&loader, args),
+        "config" => config(&config_file, &loader, args),
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// 取出 `--name value` 形式的参数
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let index = args.iter().position(|a| a == name)?;
+    args.remove(index);
+    (index < args.len()).then(|| args.remove(index))
+}
+
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    let found = args.iter().position(|a| a == name);
+    if let Some(index) = found {
+        args.remove(index);
+    }
+    found.is_some()
+}
+
+/// 取出所有 `--name value` 形式的参数
+fn take_options(args: &mut Vec<String>, name: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    while let Some(value) = take_option(args, name) {
+        values.push(value);
+    }
+    values
+}
+
+fn positional<const N: usize>(args: Vec<String>) -> Result<[String; N], Box<dyn Error>> {
+    args.try_into()
+        .map_err(|_| format!("expected {} arguments\n\n{}", N, USAGE).into())
+}
+
+fn cluster_config(loader: &ConfigLoader, cluster: &str) -> Result<ClusterConfig, Box<dyn Error>> {
+    let config = FlinkConfig::from_loaded(&load_config(loader)?)?;
+    let cluster = config
+        .clusters
+        .into_iter()
+        .find(|c| c.name == cluster)
+        .ok_or_else(|| {
+            format!(
+                "cluster {} is not configured in [[flink.clusters]]",
+                cluster
+            )
+        })?;
+    Ok(cluster)
+}
+
+fn naming_for(loader: &ConfigLoader) -> Result<NamingConvention, Box<dyn Error>> {
+    Ok(NamingConvention::new(
+        &FlinkConfig::from_loaded(&load_config(loader)?)?.naming,
+    )?)
+}
+
+fn client_for(loader: &ConfigLoader, cluster: &str) -> Result<FlinkClient, Box<dyn Error>> {
+    Ok(FlinkClient::new(cluster_config(loader, cluster)?)?)
+}
+
+fn snapshot(loader: &ConfigLoader, mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let no_config = take_flag(&mut args, "--no-config");
+    let [cluster, file] = positional(args)?;
+    let client = client_for(loader, &cluster)?;
+    let snapshot = Snapshot::capture(&client, !no_config)?;
+    snapshot.save(&file)?;
+    println!(
+        "saved {} jobs of {} to {}",
+        snapshot.jobs.len(),
+        cluster,
+        file
+    );
+    Ok(())
+}
+
+fn diff(args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let [before, after] = positional(args)?;
+    let diff = snapshot::diff(&Snapshot::load(&before)?, &Snapshot::load(&after)?);
+    print!("{}", diff);
+    Ok(())
+}
+
+fn reconcile(loader: &ConfigLoader, mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let apply = take_flag(&mut args, "--apply");
+    let allow_cancel = take_flag(&mut args, "--allow-cancel");
+    let [cluster, manifest_file] = positional(args)?;
+    let manifest = Manifest::load(&manifest_file)?;
+    let client = client_for(loader, &cluster)?;
+    let snapshot = Snapshot::capture(&client, true)?;
+    let plan = reconcile::plan(&manifest, &snapshot, allow_cancel)?;
+    print!("{}", plan);
+    if !apply {
+        println!("dry run, pass --apply to execute");
+        return Ok(());
+    }
+    let mut failed = 0;
+    for outcome in reconcile::apply(&client, &manifest, &plan) {
+        match outcome.result {
+            Ok(message) => println!("ok    {}: {}", outcome.action, message),
+            Err(e) => {
+                failed += 1;
+                println!("error {}: {}", outcome.action, e);
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{} action(s) failed", failed).into());
+    }
+    Ok(())
+}
+
+fn history(loader: &ConfigLoader, mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let filter = JobFilter::parse(&take_option(&mut args, "--filter").unwrap_or_default());
+    if args.is_empty() {
+        return Err(format!("expected a cluster\n\n{}", USAGE).into());
+    }
+    let cluster = cluster_config(loader, &args.remove(0))?;
+    let history = HistoryClient::for_cluster(&cluster).transpose()?;
+    let Some(jid) = args.first() else {
+        let history = history.ok_or("history_url is not configured for this cluster")?;
+        let mut jobs = history.archived_jobs()?;
+        naming_for(loader)?.annotate(&mut jobs);
+        for job in &jobs {
+            if filter.matches_job(job) {
+                print_job(job, JobSource::History);
+            }
+        }
+        return Ok(());
+    };
+    let live = FlinkClient::new(cluster)?;
+    match history::find_job(&live, history.as_ref(), jid)? {
+        Some((source, job)) => print_job(&job, source),
+        None => return Err(format!("job {} not found", jid).into()),
+    }
+    Ok(())
+}
+
+fn print_job(job: &Flink, source: JobSource) {
+    println!(
+        "{}  {:<12} {:<8} {}",
+        job.jid,
+        job.state,
+        format!("{:?}", source).to_lowercase(),
+        job.name
+    );
+}
+
+fn graph(loader: &ConfigLoader, mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let format = take_option(&mut args, "--format").unwrap_or("dot".to_string());
+    let no_backpressure = take_flag(&mut args, "--no-backpressure");
+    let [cluster, jid] = positional(args)?;
+    let client = client_for(loader, &cluster)?;
+    let graph = JobGraph::fetch(&client, &jid, !no_backpressure)?;
+    match format.as_str() {
+        "dot" => print!("{}", graph.to_dot()),
+        "mermaid" => print!("{}", graph.to_mermaid()),
+        _ => return Err(format!("unknown format {}, expected dot or mermaid", format).into()),
+    }
+    Ok(())
+}
+
+fn list_logs(loader: &ConfigLoader, mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    if args.is_empty() {
+        return Err(format!("expected a cluster\n\n{}", USAGE).into());
+    }
+    let client = client_for(loader, &args.remove(0))?;
+    let sources = match args.first() {
+        Some(source) => vec![LogSource::parse(source)],
+        None => {
+            let mut sources = vec![LogSource::JobManager];
+            for tm in client.taskmanagers()? {
+                sources.push(LogSource::TaskManager(tm.id));
+            }
+            sources
+        }
+    };
+    for source in sources {
+        println!("{}", source);
+        for log in logs::list_logs(&client, &source)? {
+            println!("  {:>12}  {}", log.size, log.name);
+        }
+    }
+    Ok(())
+}
+
+fn tail(loader: &ConfigLoader, mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let lines = take_option(&mut args, "-n")
+        .map(|n| n.parse())
+        .transpose()?
+        .unwrap_or(50);
+    let follow = take_flag(&mut args, "-f");
+    let pattern = take_option(&mut args, "--grep")
+        .map(|p| Regex::new(&p))
+        .transpose()?;
+    let [cluster, source, file] = positional(args)?;
+    let client = client_for(loader, &cluster)?;
+    let source = LogSource::parse(&source);
+
+    let (tail, size) = logs::tail(&client, &source, &file, lines)?;
+    for line in tail {
+        if pattern.as_ref().is_none_or(|re| re.is_match(&line)) {
+            println!("{}", line);
+        }
+    }
+    // 从末尾读到的位置继续跟踪，两次请求之间追加的内容不会丢失
+    if follow {
+        LogFollower::new(&client, source, &file, size).follow(
+            Duration::from_secs(2),
+            pattern.as_ref(),
+            |line| {
+                println!("{}", line);
+                true
+            },
+        )?;
+    }
+    Ok(())
+}
+
+fn grep(loader: &ConfigLoader, mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let file = take_option(&mut args, "--file");
+    let max_bytes = take_option(&mut args, "--max-bytes")
+        .map(|n| n.parse())
+        .transpose()?;
+    let [cluster, pattern] = positional(args)?;
+    let client = client_for(loader, &cluster)?;
+    let pattern = Regex::new(&pattern)?;
+    for found in logs::grep(&client, &pattern, file.as_deref(), max_bytes)? {
+        println!("{}", found);
+    }
+    Ok(())
+}
+
+fn sla(loader: &ConfigLoader, mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    if args.is_empty() {
+        return Err(format!("expected poll or report\n\n{}", USAGE).into());
+    }
+    match args.remove(0).as_str() {
+        "poll" => sla_poll(loader, args),
+        "report" => sla_report(loader, args),
+        other => Err(format!("unknown sla command {}\n\n{}", other, USAGE).into()),
+    }
+}
+
+fn sla_poll(loader: &ConfigLoader, mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let interval = take_option(&mut args, "--interval")
+        .map(|n| n.parse())
+        .transpose()?
+        .unwrap_or(60);
+    let once = take_flag(&mut args, "--once");
+    let [cluster, file] = positional(args)?;
+    let client = client_for(loader, &cluster)?;
+    let mut store = SlaStore::load(&file)?;
+    loop {
+        // 单次轮询失败只打印错误，超过 MAX_GAP_MS 的空档不会计入统计
+        match client.jobs_overview() {
+            Ok(jobs) => {
+                store.record(&jobs, chrono::Utc::now().timestamp_millis());
+                store.save(&file)?;
+            }
+            Err(e) if !once => eprintln!("error: {}", e),
+            Err(e) => return Err(e.into()),
+        }
+        if once {
+            return Ok(());
+        }
+        sleep(Duration::from_secs(interval));
+    }
+}
+
+fn sla_report(loader: &ConfigLoader, mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let until = match take_option(&mut args, "--until") {
+        Some(date) => chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d")?,
+        None => chrono::Utc::now().date_naive(),
+    };
+    let format = take_option(&mut args, "--format").unwrap_or("md".to_string());
+    let filter = JobFilter::parse(&take_option(&mut args, "--filter").unwrap_or_default());
+    let group_by = take_option(&mut args, "--group-by");
+    let [file] = positional(args)?;
+    let naming = naming_for(loader)?;
+    let mut report = SlaStore::load(&file)?
+        .weekly_report(until)
+        .filter(&filter, &naming);
+    if let Some(field) = group_by {
+        report = report.group_by(&naming, &field);
+    }
+    match format.as_str() {
+        "md" => print!("{}", report.to_markdown()),
+        "csv" => print!("{}", report.to_csv()),
+        _ => return Err(format!("unknown format {}, expected md or csv", format).into()),
+    }
+    Ok(())
+}
+
+fn status(loader: &ConfigLoader, mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    if args.is_empty() {
+        return Err(format!("expected record, compact or query\n\n{}", USAGE).into());
+    }
+    match args.remove(0).as_str() {
+        "record" => status_record(loader, args),
+        "compact" => status_compact(args),
+        "query" => status_query(args),
+        other => Err(format!("unknown status command {}\n\n{}", other, USAGE).into()),
+    }
+}
+
+fn status_record(loader: &ConfigLoader, mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let interval = take_option(&mut args, "--interval")
+        .map(|n| n.parse())
+        .transpose()?
+        .unwrap_or(60);
+    let once = take_flag(&mut args, "--once");
+    let [cluster, file] = positional(args)?;
+    let client = client_for(loader, &cluster)?;
+    let log = StatusLog::new(&file);
+    loop {
+        match client.jobs_overview() {
+            Ok(jobs) => log.append(&jobs, chrono::Utc::now().timestamp_millis())?,
+            Err(e) if !once => eprintln!("error: {}", e),
+            Err(e) => return Err(e.into()),
+        }
+        if once {
+            return Ok(());
+        }
+        sleep(Duration::from_secs(interval));
+    }
+}
+
+fn status_compact(mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let retention_days: i64 = take_option(&mut args, "--retention-days")
+        .map(|n| n.parse())
+        .transpose()?
+        .unwrap_or(30);
+    let [file] = positional(args)?;
+    let retain_after = chrono::Utc::now().timestamp_millis() - retention_days * 24 * 3600 * 1000;
+    let stats = StatusLog::new(&file).compact(Some(retain_after))?;
+    println!(
+        "compacted {}: {} -> {} records",
+        file, stats.before, stats.after
+    );
+    Ok(())
+}
+
+fn status_query(mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let from = take_option(&mut args, "--from")
+        .map(|t| parse_time(&t))
+        .transpose()?
+        .unwrap_or(i64::MIN);
+    let to = take_option(&mut args, "--to")
+        .map(|t| parse_time(&t))
+        .transpose()?
+        .unwrap_or(i64::MAX);
+    let [file, job] = positional(args)?;
+    let spans = StatusLog::new(&file).query(&job, from, to)?;
+    if spans.is_empty() {
+        return Err(format!("no records of {} in {}", job, file).into());
+    }
+    for span in spans {
+        println!("{}", span);
+    }
+    Ok(())
+}
+
+/// 支持 `2024-01-01`、`2024-01-01 12:00:00`（UTC）和 RFC 3339，返回毫秒时间戳
+fn parse_time(value: &str) -> Result<i64, Box<dyn Error>> {
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(time.timestamp_millis());
+    }
+    if let Ok(time) = chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S") {
+        return Ok(time.and_utc().timestamp_millis());
+    }
+    let date = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("invalid time {}", value))?;
+    Ok(date
+        .and_time(chrono::NaiveTime::MIN)
+        .and_utc()
+        .timestamp_millis())
+}
+
+fn k8s(loader: &ConfigLoader, mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    if args.is_empty() {
+        return Err(format!("expected validate, jobs or generate\n\n{}", USAGE).into());
+    }
+    match args.remove(0).as_str() {
+        "validate" => {
+            let [file] = positional(args)?;
+            let resources = operator::load_manifests(&file)?;
+            let issues: Vec<_> = resources.iter().flat_map(operator::validate).collect();
+            for issue in &issues {
+                println!("{}", issue);
+            }
+            if !issues.is_empty() {
+                return Err(format!("{} problems found in {}", issues.len(), file).into());
+            }
+            println!("{} resources in {} are valid", resources.len(), file);
+            Ok(())
+        }
+        "jobs" => {
+            let filter = JobFilter::parse(&take_option(&mut args, "--filter").unwrap_or_default());
+            let [file] = positional(args)?;
+            let mut jobs = operator::jobs(&operator::load_manifests(&file)?);
+            naming_for(loader)?.annotate(&mut jobs);
+            for job in jobs {
+                if !filter.matches_job(&job) {
+                    continue;
+                }
+                println!("{}  {:<12} {}", job.jid, job.state, job.name);
+            }
+            Ok(())
+        }
+        "generate" => {
+            let image = take_option(&mut args, "--image").ok_or("--image is required")?;
+            let flink_version =
+                take_option(&mut args, "--flink-version").unwrap_or("v1_17".to_string());
+            let namespace = take_option(&mut args, "--namespace");
+            let [manifest_file] = positional(args)?;
+            let manifest = Manifest::load(&manifest_file)?;
+            let template = operator::default_spec(&image, &flink_version);
+            let resources: Vec<OperatorResource> = manifest
+                .jobs
+                .iter()
+                .map(|job| {
+                    OperatorResource::FlinkDeployment(operator::deployment_for(
+                        job,
+                        &template,
+                        manifest.savepoint_dir.as_deref(),
+                        namespace.as_deref(),
+                    ))
+                })
+                .collect();
+            print!("{}", operator::to_yaml(&resources)?);
+            Ok(())
+        }
+        other => Err(format!("unknown k8s command {}\n\n{}", other, USAGE).into()),
+    }
+}
+
+fn storm(loader: &ConfigLoader, mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let interval = take_option(&mut args, "--interval")
+        .map(|n| n.parse())
+        .transpose()?
+        .unwrap_or(15);
+    let dry_run = take_flag(&mut args, "--dry-run");
+    let once = take_flag(&mut args, "--once");
+    let state_file = take_option(&mut args, "--state");
+    let [cluster] = positional(args)?;
+    let config = FlinkConfig::from_loaded(&load_config(loader)?)?;
+    let client = client_for(loader, &cluster)?;
+    let naming = NamingConvention::new(&config.naming)?;
+    let mut monitor = StormMonitor::new(config.storm, naming, dry_run);
+    if let Some(file) = &state_file {
+        monitor.restore(StormState::load(file)?);
+    }
+    loop {
+        match monitor.poll(&client, chrono::Utc::now().timestamp_millis()) {
+            Ok(events) => {
+                for event in events {
+                    println!("{}", event);
+                }
+            }
+            Err(e) if !once => eprintln!("error: {}", e),
+            Err(e) => return Err(e.into()),
+        }
+        if let Some(file) = &state_file {
+            monitor.state().save(file)?;
+        }
+        if once {
+            return Ok(());
+        }
+        sleep(Duration::from_secs(interval));
+    }
+}
+
+fn checkpoints(loader: &ConfigLoader, mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    if args.is_empty() {
+        return Err(format!("expected sample or report\n\n{}", USAGE).into());
+    }
+    match args.remove(0).as_str() {
+        "sample" => checkpoints_sample(loader, args),
+        "report" => checkpoints_report(loader, args),
+        other => Err(format!("unknown checkpoints command {}\n\n{}", other, USAGE).into()),
+    }
+}
+
+fn checkpoints_sample(loader: &ConfigLoader, mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let interval = take_option(&mut args, "--interval")
+        .map(|n| n.parse())
+        .transpose()?
+        .unwrap_or(60);
+    let once = take_flag(&mut args, "--once");
+    let with_alignment = take_flag(&mut args, "--alignment");
+    let [cluster, file] = positional(args)?;
+    let client = client_for(loader, &cluster)?;
+    let mut history = CheckpointHistory::load(&file)?;
+    loop {
+        let now = chrono::Utc::now().timestamp_millis();
+        match checkpoint_trend::sample_all(&client, &mut history, with_alignment, now) {
+            Ok(round) => {
+                for (name, e) in &round.errors {
+                    eprintln!("error: {}: {}", name, e);
+                }
+                if round.added > 0 {
+                    history.save(&file)?;
+                }
+            }
+            Err(e) if !once => eprintln!("error: {}", e),
+            Err(e) => return Err(e.into()),
+        }
+        if once {
+            return Ok(());
+        }
+        sleep(Duration::from_secs(interval));
+    }
+}
+
+fn checkpoints_report(loader: &ConfigLoader, mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let format = take_option(&mut args, "--format").unwrap_or("text".to_string());
+    let filter = JobFilter::parse(&take_option(&mut args, "--filter").unwrap_or_default());
+    let [file] = positional(args)?;
+    let mut history = CheckpointHistory::load(&file)?;
+    if !filter.is_empty() {
+        let naming = naming_for(loader)?;
+        history
+            .jobs
+            .retain(|name, _| filter.matches(name, &naming.parse(name)));
+    }
+    let report = TrendReport::new(&history, &TrendConfig::default());
+    match format.as_str() {
+        "text" => print!("{}", report.to_text()),
+        "json" => println!("{}", serde_json::to_string_pretty(&report.findings())?),
+        _ => return Err(format!("unknown format {}, expected text or json", format).into()),
+    }
+    Ok(())
+}
+
+fn lint(loader: &ConfigLoader, mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let cluster = take_option(&mut args, "--cluster");
+    let [file] = positional(args)?;
+    let manifest = Manifest::load(&file)?;
+    let rules = FlinkConfig::from_loaded(&load_config(loader)?)?.lint;
+    let cluster_config = match cluster {
+        Some(cluster) => client_for(loader, &cluster)?
+            .jobmanager_config()?
+            .into_iter()
+            .map(|entry| (entry.key, entry.value))
+            .collect(),
+        None => BTreeMap::new(),
+    };
+    let issues = lint::lint_manifest(&manifest, &cluster_config, &rules);
+    for issue in &issues {
+        println!("{}", issue);
+    }
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        return Err(format!(
+            "{} errors, {} warnings in {}",
+            errors,
+            issues.len() - errors,
+            file
+        )
+        .into());
+    }
+    println!(
+        "{} jobs checked, {} warnings",
+        manifest.jobs.len(),
+        issues.len()
+    );
+    Ok(())
+}
+
+fn capacity(loader: &ConfigLoader, mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let fit = take_option(&mut args, "--fit")
+        .map(|n| n.parse::<i32>())
+        .transpose()?;
+    let lose = take_option(&mut args, "--lose");
+    let no_placement = take_flag(&mut args, "--no-placement");
+    let [cluster] = positional(args)?;
+    let client = client_for(loader, &cluster)?;
+    // 只有回答 TaskManager 故障的问题时才需要作业的部署位置
+    let with_placement = lose.is_some() && !no_placement;
+    let model = CapacityModel::fetch(&client, with_placement)?;
+    print!("{}", model);
+    if let Some(parallelism) = fit {
+        println!("{}", model.fit(parallelism));
+    }
+    if let Some(id) = lose {
+        let answer = model
+            .lose_taskmanager(&id)
+            .ok_or_else(|| format!("TaskManager {} not found in {}", id, cluster))?;
+        println!("{}", answer);
+    }
+    Ok(())
+}
+
+/// 使用 `--profile`、`--set` 和 `--config-format` 参数的分层配置加载器，所有命令共用
+fn loader_for(config_file: &str, args: &mut Vec<String>) -> Result<ConfigLoader, String> {
+    Ok(ConfigLoader::new(config_file)
+        .with_format(format_option(args, "--config-format")?)
+        .with_profile(take_option(args, "--profile"))
+        .with_overrides(take_options(args, "--set")))
+}
+
+/// 读取合并后的配置；一个命令可能读取多次，迁移警告只输出一次
+fn load_config(loader: &ConfigLoader) -> Result<LoadedConfig, ConfigError> {
+    static WARNED: Once = Once::new();
+    let loaded = loader.load()?;
+    WARNED.call_once(|| {
+        for warning in loaded.warnings() {
+            eprintln!("warning: {}", warning);
+        }
+    });
+    Ok(loaded)
+}
+
+fn format_option(args: &mut Vec<String>, name: &str) -> Result<Option<Format>, String> {
+    take_option(args, name).map(|s| s.parse()).transpose()
+}
+
+fn config(
+    config_file: &str,
+    loader: &ConfigLoader,
+    mut args: Vec<String>,
+) -> Result<(), Box<dyn Error>> {
+    if args.is_empty() {
+        return Err(format!(
+            "expected get, show, features, check, watch, init, migrate, convert, schema, keygen or encrypt\n\n{}",
+            USAGE
+        )
+        .into());
+    }
+    let command = args.remove(0);
+    match command.as_str() {
+        "get" => {
+            let [key] = positional(args)?;
+            let loaded = load_config(loader)?;
+            let value = loaded
+                .get(&key)
+                .ok_or_else(|| format!("{} is not set", key))?;
+            println!("{} = {}", key, value);
+            if let Some(source) = loaded.source(&key) {
+                println!("# from {}", source);
+            }
+            Ok(())
+        }
+        "show" => {
+            positional::<0>(args)?;
+            let loaded = load_config(loader)?;
+            let mut entries = loaded.effective();
+            let flink = FlinkConfig::from_loaded(&loaded)?;
+            entries.extend(loaded.section_entries("flink", toml::Table::try_from(flink)?));
+            let width = entries
+                .iter()
+                .map(|entry| entry.key.len() + entry.value.to_string().len() + 3)
+                .max()
+                .unwrap_or(0);
+            for entry in entries {
+                let line = format!("{} = {}", entry.key, entry.value);
+                println!("{:<width$}  # {}", line, entry.origin, width = width);
+            }
+            Ok(())
+        }
+        "features" => {
+            let subject = take_option(&mut args, "--subject");
+            positional::<0>(args)?;
+            let profile = loader.profile().map(String::from);
+            let config = load_config(loader)?.config;
+            let flags = FeatureFlags::fixed(config.clone(), profile.clone());
+            for (name, toggle) in &config.features {
+                let enabled = match &subject {
+                    Some(subject) => flags.is_enabled_for(name, subject),
+                    None => flags.is_enabled(name),
+                };
+                let rollout = match toggle.rollout(profile.as_deref()) {
+                    Rollout::Enabled(enabled) => enabled.to_string(),
+                    Rollout::Percent(percent) => format!("{}%", percent),
+                };
+                println!(
+                    "{:<30} {:<5} ({})",
+                    name,
+                    if enabled { "on" } else { "off" },
+                    rollout
+                );
+            }
+            Ok(())
+        }
+        "check" => {
+            positional::<0>(args)?;
+            let loaded = load_config(loader)?;
+            let issues = loaded.config.validate();
+            for issue in &issues {
+                match loaded.source(&issue.key) {
+                    Some(source) => println!("{} (from {})", issue, source),
+                    None => println!("{}", issue),
+                }
+            }
+            if !issues.is_empty() {
+                return Err(format!("{} problems found", issues.len()).into());
+            }
+            println!("config is valid");
+            Ok(())
+        }
+        "watch" => {
+            let interval = take_option(&mut args, "--interval")
+                .map(|s| s.parse::<u64>())
+                .transpose()?
+                .unwrap_or(2);
+            positional::<0>(args)?;
+            let watcher = ConfigWatcher::new(loader.clone())
+                .map_err(|problems| format!("invalid config:\n  {}", problems.join("\n  ")))?;
+            for warning in watcher.current().warnings() {
+                eprintln!("warning: {}", warning);
+            }
+            let receiver = watcher.subscribe();
+            println!("watching {:?}", **receiver.borrow());
+            watcher
+                .spawn(Duration::from_secs(interval))
+                .join()
+                .map_err(|_| "config watcher panicked")?;
+            Ok(())
+        }
+        "init" => {
+            let force = take_flag(&mut args, "--force");
+            let mut args = args.into_iter();
+            let (path, None) = (args.next(), args.next()) else {
+                return Err(format!("expected at most 1 argument\n\n{}", USAGE).into());
+            };
+            let path = path.unwrap_or_else(|| config_file.to_string());
+            if Format::for_path(&path) != Format::Toml {
+                return Err(format!(
+                    "{} is not a .toml file, use config convert after config init",
+                    path
+                )
+                .into());
+            }
+            if Path::new(&path).exists() && !force {
+                return Err(format!("{} already exists, use --force to overwrite it", path).into());
+            }
+            fs::write(&path, schema::template())?;
+            println!("wrote the default config to {}", path);
+            Ok(())
+        }
+        "migrate" => {
+            let [path] = positional(args)?;
+            let format = Format::for_path(&path);
+            let mut table = format.parse_table(&path, &fs::read_to_string(&path)?)?;
+            let applied = migrate::migrate(&path, &mut table)?;
+            if applied.is_empty() {
+                eprintln!("{} is already at version {}", path, CURRENT_VERSION);
+            }
+            for description in applied {
+                eprintln!("migrated: {}", description);
+            }
+            print!("{}", format.render(&table));
+            Ok(())
+        }
+        "convert" => {
+            let from = format_option(&mut args, "--from")?;
+            let to = format_option(&mut args, "--to")?;
+            let mut args = args.into_iter();
+            let (Some(input), output, None) = (args.next(), args.next(), args.next()) else {
+                return Err(format!("expected 1 or 2 arguments\n\n{}", USAGE).into());
+            };
+            let from = from.unwrap_or_else(|| Format::for_path(&input));
+            let to = to
+                .or_else(|| output.as_deref().and_then(Format::from_path))
+                .ok_or("--to is required when the output has no known extension")?;
+            let table = from.parse_table(&input, &fs::read_to_string(&input)?)?;
+            let text = to.render(&table);
+            match output {
+                Some(output) if Path::new(&output).exists() => {
+                    return Err(format!("{} already exists", output).into());
+                }
+                Some(output) => {
+                    fs::write(&output, text)?;
+                    println!("converted {} ({}) to {} ({})", input, from, output, to);
+                }
+                None => print!("{}", text),
+            }
+            Ok(())
+        }
+        "schema" => {
+            let format = take_option(&mut args, "--format").unwrap_or(String::from("json"));
+            positional::<0>(args)?;
+            match format.as_str() {
+                "json" => println!("{}", serde_json::to_string_pretty(&schema::json_schema())?),
+                "markdown" => print!("{}", schema::markdown()),
+                _ => {
+                    return Err(
+                        format!("unknown format {}, expected json or markdown", format).into(),
+                    );
+                }
+            }
+            Ok(())
+        }
+        "keygen" => {
+            let [path] = positional(args)?;
+            // 创建时就只有所有者可读（Unix），已存在时失败而不是覆盖
+            let mut options = fs::OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            options.mode(0o600);
+            let mut file = options
+                .open(&path)
+                .map_err(|e| format!("failed to create {}: {}", path, e))?;
+            file.write_all(secret::generate_key().as_bytes())?;
+            println!("wrote a new key to {}", path);
+            Ok(())
+        }
+        "encrypt" => {
+            let resolver = match take_option(&mut args, "--key-file") {
+                Some(path) => SecretResolver::new(Some(PathBuf::from(path))),
+                None => SecretResolver::from_env(),
+            };
+            positional::<0>(args)?;
+            // 从标准输入读取，明文不会出现在 ps 和 shell 历史中
+            let mut value = String::new();
+            io::stdin().read_to_string(&mut value)?;
+            let value = value.trim_end_matches(['\r', '\n']);
+            if value.is_empty() {
+                return Err("expected the value to encrypt on stdin".into());
+            }
+            println!("{}", secret::encrypt(&resolver.key()?, value));
+            Ok(())
+        }
+        other => Err(format!("unknown config command {}\n\n{}", other, USAGE).into()),
+    }
+}
@@ -0,0 +1,736 @@
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::DefaultTerminal;
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Wrap};
+use rust_project::checkpoint_trend::format_bytes;
+use rust_project::client::flink::{FlinkClient, FlinkConfig};
+use rust_project::config::ConfigLoader;
+use rust_project::models::enums::JobState;
+use rust_project::models::flink::{CheckpointStats, ExceptionEntry, Flink, JobDetail};
+use rust_project::naming::{JobFilter, NamingConvention};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const CONFIG_FILE: &str = "config.toml";
+const DEFAULT_INTERVAL_SECS: u64 = 5;
+
+// 用法: flink_dashboard [config.toml] [--interval 秒]
+fn main() {
+    let (config_file, interval) = parse_args(std::env::args().skip(1).collect());
+    let loaded = match ConfigLoader::new(&config_file).load() {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    for warning in loaded.warnings() {
+        eprintln!("warning: {}", warning);
+    }
+    let config = match FlinkConfig::from_loaded(&loaded) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    if config.clusters.is_empty() {
+        eprintln!("no [[flink.clusters]] configured in {}", config_file);
+        std::process::exit(1);
+    }
+    let naming = match NamingConvention::new(&config.naming) {
+        Ok(naming) => naming,
+        Err(e) => {
+            eprintln!("invalid [flink.naming] pattern: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let mut clients = Vec::new();
+    for cluster in config.clusters {
+        match FlinkClient::new(cluster) {
+            Ok(client) => clients.push(client),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let cluster_names = clients.iter().map(|c| c.cluster().name.clone()).collect();
+    let (command_tx, command_rx) = mpsc::channel();
+    let (update_tx, update_rx) = mpsc::channel();
+    thread::spawn(move || worker(clients, interval, command_rx, update_tx));
+
+    let mut terminal = ratatui::init();
+    let result = App::new(cluster_names, naming).run(&mut terminal, command_tx, update_rx);
+    ratatui::restore();
+    if let Err(e) = result {
+        eprintln!("{}", e);
+    }
+}
+
+fn parse_args(args: Vec<String>) -> (String, Duration) {
+    let mut config_file = String::from(CONFIG_FILE);
+    let mut interval = DEFAULT_INTERVAL_SECS;
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--interval" {
+            interval = iter
+                .next()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_INTERVAL_SECS);
+        } else {
+            config_file = arg;
+        }
+    }
+    (config_file, Duration::from_secs(interval.max(1)))
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Refresh,
+    Detail { cluster: usize, jid: String },
+}
+
+enum Update {
+    Jobs {
+        cluster: usize,
+        result: Result<Vec<Flink>, String>,
+    },
+    Detail(Box<DetailView>),
+}
+
+struct DetailView {
+    cluster: usize,
+    jid: String,
+    detail: Result<JobDetail, String>,
+    checkpoints: Result<CheckpointStats, String>,
+    exception: Result<Option<ExceptionEntry>, String>,
+}
+
+/// 后台线程：定时拉取所有集群的 overview，并按需拉取作业详情
+fn worker(
+    clients: Vec<FlinkClient>,
+    interval: Duration,
+    commands: Receiver<Command>,
+    updates: Sender<Update>,
+) {
+    let refresh = |updates: &Sender<Update>| {
+        for (cluster, client) in clients.iter().enumerate() {
+            let result = client.jobs_overview().map_err(|e| e.to_string());
+            if updates.send(Update::Jobs { cluster, result }).is_err() {
+                return false;
+            }
+        }
+        true
+    };
+    if !refresh(&updates) {
+        return;
+    }
+    loop {
+        let first = match commands.recv_timeout(interval) {
+            Ok(command) => command,
+            Err(RecvTimeoutError::Timeout) => Command::Refresh,
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        for command in coalesce(first, &commands) {
+            let ok = match command {
+                Command::Refresh => refresh(&updates),
+                Command::Detail { cluster, jid } => {
+                    let client = &clients[cluster];
+                    let view = DetailView {
+                        cluster,
+                        detail: client.job_detail(&jid).map_err(|e| e.to_string()),
+                        checkpoints: client.job_checkpoints(&jid).map_err(|e| e.to_string()),
+                        exception: client
+                            .job_exceptions(&jid)
+                            .map(|e| e.latest())
+                            .map_err(|e| e.to_string()),
+                        jid,
+                    };
+                    updates.send(Update::Detail(Box::new(view))).is_ok()
+                }
+            };
+            if !ok {
+                return;
+            }
+        }
+    }
+}
+
+/// 取出已积压的命令合并执行：刷新最多一次，详情只查询最后选中的作业；
+/// 快速按上下键时每次移动都会发送 Detail，逐个查询会让界面落后于选择
+fn coalesce(first: Command, commands: &Receiver<Command>) -> Vec<Command> {
+    let mut refresh = false;
+    let mut detail = None;
+    for command in std::iter::once(first).chain(commands.try_iter()) {
+        match command {
+            Command::Refresh => refresh = true,
+            command => detail = Some(command),
+        }
+    }
+    let mut coalesced = Vec::new();
+    if refresh {
+        coalesced.push(Command::Refresh);
+    }
+    coalesced.extend(detail);
+    coalesced
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SortKey {
+    Cluster,
+    Name,
+    State,
+    StartTime,
+    Failed,
+}
+
+impl SortKey {
+    fn next(self) -> SortKey {
+        match self {
+            SortKey::Cluster => SortKey::Name,
+            SortKey::Name => SortKey::State,
+            SortKey::State => SortKey::StartTime,
+            SortKey::StartTime => SortKey::Failed,
+            SortKey::Failed => SortKey::Cluster,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SortKey::Cluster => "cluster",
+            SortKey::Name => "name",
+            SortKey::State => "state",
+            SortKey::StartTime => "start time",
+            SortKey::Failed => "failed tasks",
+        }
+    }
+}
+
+struct JobRow {
+    cluster: usize,
+    job: Flink,
+}
+
+struct App {
+    clusters: Vec<String>,
+    jobs: Vec<JobRow>,
+    errors: Vec<Option<String>>,
+    sort: SortKey,
+    descending: bool,
+    filter: String,
+    editing_filter: bool,
+    naming: NamingConvention,
+    table: TableState,
+    show_detail: bool,
+    detail: Option<Box<DetailView>>,
+    last_refresh: Option<Instant>,
+}
+
+impl App {
+    fn new(clusters: Vec<String>, naming: NamingConvention) -> App {
+        let errors = vec![None; clusters.len()];
+        App {
+            clusters,
+            jobs: Vec::new(),
+            errors,
+            sort: SortKey::Cluster,
+            descending: false,
+            filter: String::new(),
+            editing_filter: false,
+            naming,
+            table: TableState::default().with_selected(Some(0)),
+            show_detail: false,
+            detail: None,
+            last_refresh: None,
+        }
+    }
+
+    fn run(
+        mut self,
+        terminal: &mut DefaultTerminal,
+        commands: Sender<Command>,
+        updates: Receiver<Update>,
+    ) -> std::io::Result<()> {
+        loop {
+            while let Ok(update) = updates.try_recv() {
+                self.apply(update, &commands);
+            }
+            terminal.draw(|frame| self.draw(frame))?;
+            if !event::poll(Duration::from_millis(200))? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if self.editing_filter {
+                match key.code {
+                    KeyCode::Esc | KeyCode::Enter => self.editing_filter = false,
+                    KeyCode::Backspace => {
+                        self.filter.pop();
+                    }
+                    KeyCode::Char(c) => self.filter.push(c),
+                    _ => {}
+                }
+                self.table.select(Some(0));
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Down | KeyCode::Char('j') => self.table.select_next(),
+                KeyCode::Up | KeyCode::Char('k') => self.table.select_previous(),
+                KeyCode::Char('/') => self.editing_filter = true,
+                KeyCode::Char('s') => self.sort = self.sort.next(),
+                KeyCode::Char('r') => self.descending = !self.descending,
+                KeyCode::Char('R') | KeyCode::F(5) => {
+                    let _ = commands.send(Command::Refresh);
+                }
+                KeyCode::Enter => {
+                    self.show_detail = !self.show_detail;
+                    self.request_detail(&commands);
+                }
+                _ => {}
+            }
+            if self.show_detail
+                && matches!(
+                    key.code,
+                    KeyCode::Up | KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('k')
+                )
+            {
+                self.request_detail(&commands);
+            }
+        }
+    }
+
+    fn apply(&mut self, update: Update, commands: &Sender<Command>) {
+        match update {
+            Update::Jobs { cluster, result } => {
+                match result {
+                    Ok(mut jobs) => {
+                        self.naming.annotate(&mut jobs);
+                        self.jobs.retain(|row| row.cluster != cluster);
+                        self.jobs
+                            .extend(jobs.into_iter().map(|job| JobRow { cluster, job }));
+                        self.errors[cluster] = None;
+                        // 失败时保留上次成功的时间，旧数据不会显得像刚刷新过
+                        self.last_refresh = Some(Instant::now());
+                    }
+                    Err(e) => self.errors[cluster] = Some(e),
+                }
+                if cluster + 1 == self.clusters.len() && self.show_detail {
+                    self.request_detail(commands);
+                }
+            }
+            Update::Detail(view) => self.detail = Some(view),
+        }
+    }
+
+    fn selected(&self) -> Option<&JobRow> {
+        let visible = visible_jobs(&self.jobs, &self.filter, self.sort, self.descending);
+        self.table.selected().and_then(|i| visible.get(i).copied())
+    }
+
+    fn request_detail(&self, commands: &Sender<Command>) {
+        if !self.show_detail {
+            return;
+        }
+        if let Some(row) = self.selected() {
+            let _ = commands.send(Command::Detail {
+                cluster: row.cluster,
+                jid: row.job.jid.clone(),
+            });
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(5),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        self.draw_header(frame, header);
+        if self.show_detail {
+            let [table, detail] =
+                Layout::vertical([Constraint::Percentage(55), Constraint::Percentage(45)])
+                    .areas(body);
+            self.draw_table(frame, table);
+            self.draw_detail(frame, detail);
+        } else {
+            self.draw_table(frame, body);
+        }
+
+        let help = if self.editing_filter {
+            format!("filter: {}_  (Enter/Esc to finish)", self.filter)
+        } else {
+            format!(
+                "q quit | j/k move | Enter detail | / filter [{}] | s sort [{}{}] | r reverse | R refresh",
+                self.filter,
+                self.sort.label(),
+                if self.descending { " desc" } else { "" }
+            )
+        };
+        frame.render_widget(Paragraph::new(help), footer);
+    }
+
+    fn draw_header(&self, frame: &mut Frame, area: Rect) {
+        let mut spans = vec![Span::styled(
+            " Flink dashboard ",
+            Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED),
+        )];
+        for (i, name) in self.clusters.iter().enumerate() {
+            let (text, color) = match &self.errors[i] {
+                Some(_) => (format!(" {}: unreachable ", name), Color::Red),
+                None => {
+                    let count = self.jobs.iter().filter(|row| row.cluster == i).count();
+                    (format!(" {}: {} jobs ", name, count), Color::Green)
+                }
+            };
+            spans.push(Span::styled(text, Style::default().fg(color)));
+        }
+        if let Some(last) = self.last_refresh {
+            spans.push(Span::raw(format!(
+                " refreshed {}s ago",
+                last.elapsed().as_secs()
+            )));
+        }
+        frame.render_widget(Paragraph::new(Line::from(spans)), area);
+    }
+
+    fn draw_table(&mut self, frame: &mut Frame, area: Rect) {
+        let visible = visible_jobs(&self.jobs, &self.filter, self.sort, self.descending);
+        let rows: Vec<Row> = visible
+            .iter()
+            .map(|row| {
+                let job = &row.job;
+                let tasks = &job.tasks;
+                Row::new(vec![
+                    Cell::from(self.clusters[row.cluster].clone()),
+                    Cell::from(job.name.clone()),
+                    Cell::from(job.state.clone())
+                        .style(Style::default().fg(state_color(job.job_state()))),
+                    Cell::from(format!("{}/{}", tasks.running, tasks.total)),
+                    Cell::from(tasks.deploying.to_string()),
+                    Cell::from(tasks.failed.to_string()).style(if tasks.failed > 0 {
+                        Style::default().fg(Color::Red)
+                    } else {
+                        Style::default()
+                    }),
+                    Cell::from(tasks.canceled.to_string()),
+                    Cell::from(format_duration_ms(
+                        job.duration
+                            .as_deref()
+                            .and_then(|d| d.parse().ok())
+                            .unwrap_or_default(),
+                    )),
+                ])
+            })
+            .collect();
+        if self.table.selected().is_some_and(|i| i >= rows.len()) {
+            self.table.select(Some(rows.len().saturating_sub(1)));
+        }
+
+        let header = Row::new(vec![
+            "Cluster",
+            "Name",
+            "State",
+            "Running",
+            "Deploying",
+            "Failed",
+            "Canceled",
+            "Duration",
+        ])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+        let widths = [
+            Constraint::Length(10),
+            Constraint::Min(20),
+            Constraint::Length(12),
+            Constraint::Length(9),
+            Constraint::Length(10),
+            Constraint::Length(7),
+            Constraint::Length(9),
+            Constraint::Length(14),
+        ];
+        let title = format!(" Jobs ({}/{}) ", rows.len(), self.jobs.len());
+        let table = Table::new(rows, widths)
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+        frame.render_stateful_widget(table, area, &mut self.table);
+    }
+
+    fn draw_detail(&self, frame: &mut Frame, area: Rect) {
+        let [vertices_area, checkpoints_area, exception_area] = Layout::horizontal([
+            Constraint::Percentage(40),
+            Constraint::Percentage(25),
+            Constraint::Percentage(35),
+        ])
+        .areas(area);
+        let block = |title: &str| {
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title.to_string())
+        };
+
+        let selected = self
+            .selected()
+            .map(|row| (row.cluster, row.job.jid.as_str()));
+        let view = self
+            .detail
+            .as_ref()
+            .filter(|view| Some((view.cluster, view.jid.as_str())) == selected);
+        let Some(view) = view else {
+            frame.render_widget(Paragraph::new("loading...").block(block(" Detail ")), area);
+            return;
+        };
+
+        let vertices: Vec<Line> = match &view.detail {
+            Ok(detail) => detail
+                .vertices
+                .iter()
+                .map(|v| {
+                    Line::from(vec![
+                        Span::styled(
+                            format!("{:<12}", v.status),
+                            Style::default().fg(state_color(JobState::from(v.status.as_str()))),
+                        ),
+                        Span::raw(format!("p={:<4} {}", v.parallelism, v.name)),
+                    ])
+                })
+                .collect(),
+            Err(e) => vec![Line::from(e.clone())],
+        };
+        frame.render_widget(
+            Paragraph::new(vertices)
+                .block(block(" Vertices "))
+                .wrap(Wrap { trim: true }),
+            vertices_area,
+        );
+
+        let checkpoints: Vec<Line> = match &view.checkpoints {
+            Ok(stats) => {
+                let mut lines = vec![
+                    Line::from(format!("completed: {}", stats.counts.completed)),
+                    Line::from(format!("failed:    {}", stats.counts.failed)),
+                    Line::from(format!("in progress: {}", stats.counts.in_progress)),
+                ];
+                if let Some(latest) = &stats.latest.completed {
+                    let age = now_millis() - latest.latest_ack_timestamp;
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(format!("latest #{}", latest.id)));
+                    lines.push(Line::from(format!(
+                        "  size: {}",
+                        format_bytes(latest.state_size)
+                    )));
+                    lines.push(Line::from(format!(
+                        "  took: {}",
+                        format_duration_ms(latest.end_to_end_duration)
+                    )));
+                    lines.push(Line::from(format!("  age:  {}", format_duration_ms(age))));
+                }
+                lines
+            }
+            Err(e) => vec![Line::from(e.clone())],
+        };
+        frame.render_widget(
+            Paragraph::new(checkpoints)
+                .block(block(" Checkpoints "))
+                .wrap(Wrap { trim: true }),
+            checkpoints_area,
+        );
+
+        let exception = match &view.exception {
+            Ok(Some(entry)) => {
+                let age = format_duration_ms(now_millis() - entry.timestamp);
+                let mut lines = vec![
+                    Line::styled(
+                        entry.exception_name.clone(),
+                        Style::default().fg(Color::Red),
+                    ),
+                    Line::from(format!(
+                        "{} ago {}",
+                        age,
+                        entry.task_name.as_deref().unwrap_or_default()
+                    )),
+                ];
+                lines.extend(
+                    entry
+                        .stacktrace
+                        .lines()
+                        .skip(1)
+                        .map(|l| Line::from(l.to_string())),
+                );
+                lines
+            }
+            Ok(None) => vec![Line::from("no exceptions")],
+            Err(e) => vec![Line::from(e.clone())],
+        };
+        frame.render_widget(
+            Paragraph::new(exception)
+                .block(block(" Latest exception "))
+                .wrap(Wrap { trim: false }),
+            exception_area,
+        );
+    }
+}
+
+/// 按名称或命名规则解析出的字段过滤（见 [`JobFilter`]）并排序
+fn visible_jobs<'a>(
+    jobs: &'a [JobRow],
+    filter: &str,
+    sort: SortKey,
+    descending: bool,
+) -> Vec<&'a JobRow> {
+    let filter = JobFilter::parse(filter);
+    let mut visible: Vec<&JobRow> = jobs
+        .iter()
+        .filter(|row| filter.matches_job(&row.job))
+        .collect();
+    visible.sort_by(|a, b| {
+        let ordering = match sort {
+            SortKey::Cluster => a.cluster.cmp(&b.cluster),
+            SortKey::Name => a.job.name.cmp(&b.job.name),
+            SortKey::State => a.job.job_state().cmp(&b.job.job_state()),
+            SortKey::StartTime => a.job.start_time.cmp(&b.job.start_time),
+            SortKey::Failed => a.job.tasks.failed.cmp(&b.job.tasks.failed),
+        }
+        .then_with(|| a.job.name.cmp(&b.job.name));
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+    visible
+}
+
+fn state_color(state: JobState) -> Color {
+    match state {
+        JobState::Running => Color::Green,
+        JobState::Finished => Color::Blue,
+        JobState::Failed | JobState::Failing => Color::Red,
+        JobState::Restarting | JobState::Cancelling => Color::Yellow,
+        JobState::Canceled => Color::DarkGray,
+        JobState::Suspended => Color::Magenta,
+        JobState::Initializing | JobState::Created | JobState::Reconciling => Color::Cyan,
+        JobState::Unknown => Color::White,
+    }
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+fn format_duration_ms(ms: i64) -> String {
+    if ms < 0 {
+        return String::from("-");
+    }
+    let secs = ms / 1000;
+    let (days, hours, minutes, seconds) =
+        (secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    if days > 0 {
+        format!("{}d {:02}:{:02}:{:02}", days, hours, minutes, seconds)
+    } else if secs > 0 {
+        format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}ms", ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(name: &str, state: &str, failed: i32) -> Flink {
+        let json = format!(
+            r#"{{"jid":"{name}","name":"{name}","state":"{state}","start-time":1,
+                "tasks":{{"total":1,"created":0,"scheduled":0,"deploying":0,"running":1,"finished":0,
+                "canceling":0,"canceled":0,"failed":{failed},"reconciling":0,"initializing":0}}}}"#
+        );
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn test_visible_jobs_filter_and_sort() {
+        let mut jobs = vec![
+            JobRow {
+                cluster: 0,
+                job: job("orders-etl", "RUNNING", 0),
+            },
+            JobRow {
+                cluster: 1,
+                job: job("Orders-sync", "FAILED", 3),
+            },
+            JobRow {
+                cluster: 0,
+                job: job("payments", "RUNNING", 1),
+            },
+        ];
+        let names =
+            |rows: Vec<&JobRow>| rows.iter().map(|r| r.job.name.clone()).collect::<Vec<_>>();
+
+        let naming = NamingConvention::new(&rust_project::naming::NamingConfig {
+            patterns: vec![r"^(?P<app>[a-z]+)-(?P<kind>[a-z]+)$".to_string()],
+        })
+        .unwrap();
+        naming.annotate(jobs.iter_mut().map(|row| &mut row.job));
+
+        assert_eq!(
+            names(visible_jobs(&jobs, "ORDERS", SortKey::Name, false)),
+            vec!["Orders-sync", "orders-etl"]
+        );
+        assert_eq!(
+            names(visible_jobs(&jobs, "kind=etl", SortKey::Name, false)),
+            vec!["orders-etl"]
+        );
+        assert_eq!(
+            names(visible_jobs(&jobs, "", SortKey::Failed, true)),
+            vec!["Orders-sync", "payments", "orders-etl"]
+        );
+    }
+
+    #[test]
+    fn test_format_duration_ms() {
+        assert_eq!(format_duration_ms(-1), "-");
+        assert_eq!(format_duration_ms(250), "250ms");
+        assert_eq!(format_duration_ms(3_723_000), "01:02:03");
+        assert_eq!(format_duration_ms(90_061_000), "1d 01:01:01");
+    }
+
+    #[test]
+    fn test_coalesce() {
+        let (tx, rx) = mpsc::channel();
+        for jid in ["a", "b", "c"] {
+            tx.send(Command::Detail {
+                cluster: 0,
+                jid: jid.to_string(),
+            })
+            .unwrap();
+        }
+        tx.send(Command::Refresh).unwrap();
+        assert_eq!(
+            coalesce(Command::Refresh, &rx),
+            vec![
+                Command::Refresh,
+                Command::Detail {
+                    cluster: 0,
+                    jid: "c".to_string()
+                }
+            ]
+        );
+        assert!(rx.try_recv().is_err());
+    }
+}
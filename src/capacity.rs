@@ -0,0 +1,343 @@
+use crate::client::error::FlinkError;
+use crate::client::flink::FlinkClient;
+use crate::models::cluster::ClusterOverview;
+use crate::models::enums::JobState;
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// TaskManager 的 slot 和硬件资源
+#[derive(Debug, Clone)]
+pub struct TaskManagerCapacity {
+    pub id: String,
+    pub slots: i32,
+    pub free_slots: i32,
+    pub cpu_cores: Option<i32>,
+    pub physical_memory: Option<i64>,
+}
+
+impl TaskManagerCapacity {
+    pub fn used_slots(&self) -> i32 {
+        self.slots - self.free_slots
+    }
+}
+
+/// 运行中的作业占用的 slot；默认所有算子共享 slot，占用数等于最大并行度
+#[derive(Debug, Clone)]
+pub struct JobFootprint {
+    pub jid: String,
+    pub name: String,
+    pub slots: i32,
+    /// 作业的 subtask 所在的 TaskManager；未采集部署位置，或接口没有返回 TaskManager id（老版本）时为 `None`
+    pub taskmanagers: Option<BTreeSet<String>>,
+}
+
+/// 集群容量模型，所有的“如果……会怎样”都基于这一份数据计算
+#[derive(Debug, Clone)]
+pub struct CapacityModel {
+    pub cluster: String,
+    pub overview: ClusterOverview,
+    pub taskmanagers: Vec<TaskManagerCapacity>,
+    pub jobs: Vec<JobFootprint>,
+    /// 是否采集了作业的部署位置
+    pub with_placement: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FitAnswer {
+    pub parallelism: i32,
+    pub free_slots: i32,
+    pub fits: bool,
+    /// 放入新作业后剩余的 slot
+    pub headroom: i32,
+    /// 放入新作业后，任意一个 TaskManager 故障时其上的作业是否还能重新调度
+    pub survives_taskmanager_loss: bool,
+}
+
+impl fmt::Display for FitAnswer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.fits {
+            return write!(
+                f,
+                "a job with parallelism {} does NOT fit: {} free slots, {} short",
+                self.parallelism, self.free_slots, -self.headroom
+            );
+        }
+        write!(
+            f,
+            "a job with parallelism {} fits: {} free slots, {} left afterwards",
+            self.parallelism, self.free_slots, self.headroom
+        )?;
+        if !self.survives_taskmanager_loss {
+            f.write_str(", but the cluster could not recover from losing its largest TaskManager")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailureAnswer {
+    pub taskmanager: String,
+    /// 故障 TaskManager 上被占用的 slot，需要在其它 TaskManager 上重新申请
+    pub lost_slots: i32,
+    /// 其它 TaskManager 上的空闲 slot
+    pub free_slots: i32,
+    pub recoverable: bool,
+    /// 有 subtask 在该 TaskManager 上的作业，不知道部署位置时为 `None`
+    pub affected_jobs: Option<Vec<String>>,
+}
+
+impl fmt::Display for FailureAnswer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "losing {} frees {} used slots, {} free slots remain elsewhere: {}",
+            self.taskmanager,
+            self.lost_slots,
+            self.free_slots,
+            if self.recoverable {
+                "all jobs can be rescheduled"
+            } else {
+                "some jobs will stay unscheduled"
+            }
+        )?;
+        match &self.affected_jobs {
+            None => write!(f, "\naffected jobs: unknown (placement not fetched)"),
+            Some(jobs) if jobs.is_empty() => write!(f, "\naffected jobs: none"),
+            Some(jobs) => write!(f, "\naffected jobs: {}", jobs.join(", ")),
+        }
+    }
+}
+
+impl CapacityModel {
+    /// `with_placement` 为 true 时逐个顶点查询 subtask 所在的 TaskManager，请求较多
+    pub fn fetch(client: &FlinkClient, with_placement: bool) -> Result<CapacityModel, FlinkError> {
+        let overview = client.overview()?;
+        let taskmanagers = client
+            .taskmanagers()?
+            .into_iter()
+            .map(|tm| TaskManagerCapacity {
+                id: tm.id,
+                slots: tm.slots_number,
+                free_slots: tm.free_slots,
+                cpu_cores: tm.hardware.as_ref().map(|h| h.cpu_cores),
+                physical_memory: tm.hardware.as_ref().map(|h| h.physical_memory),
+            })
+            .collect();
+
+        let mut jobs = Vec::new();
+        for job in client.jobs_overview()? {
+            if job.job_state().is_terminal() || job.job_state() == JobState::Suspended {
+                continue;
+            }
+            // 作业可能在列出之后结束并被清理，此时不再占用 slot
+            let detail = match client.job_detail(&job.jid) {
+                Ok(detail) => detail,
+                Err(e) if e.is_not_found() => continue,
+                Err(e) => return Err(e),
+            };
+            let slots = detail
+                .vertices
+                .iter()
+                .map(|v| v.parallelism)
+                .max()
+                .unwrap_or_default();
+            let mut placement = None;
+            if with_placement {
+                let mut found = Some(BTreeSet::new());
+                for vertex in &detail.vertices {
+                    let taskmanagers = match client.vertex_taskmanagers(&job.jid, &vertex.id) {
+                        Ok(taskmanagers) => taskmanagers,
+                        Err(e) if e.is_not_found() => Vec::new(),
+                        Err(e) => return Err(e),
+                    };
+                    for tm in taskmanagers {
+                        // 只有主机名时无法对应到 TaskManager id，部署位置视为未知
+                        match (tm.taskmanager_id, found.as_mut()) {
+                            (Some(id), Some(found)) => {
+                                found.insert(id);
+                            }
+                            _ => found = None,
+                        }
+                    }
+                }
+                placement = found;
+            }
+            jobs.push(JobFootprint {
+                jid: job.jid,
+                name: job.name,
+                slots,
+                taskmanagers: placement,
+            });
+        }
+        Ok(CapacityModel {
+            cluster: client.cluster().name.clone(),
+            overview,
+            taskmanagers,
+            jobs,
+            with_placement,
+        })
+    }
+
+    pub fn total_slots(&self) -> i32 {
+        self.taskmanagers.iter().map(|tm| tm.slots).sum()
+    }
+
+    pub fn free_slots(&self) -> i32 {
+        self.taskmanagers.iter().map(|tm| tm.free_slots).sum()
+    }
+
+    /// 能否再放入一个并行度为 `parallelism` 的作业
+    pub fn fit(&self, parallelism: i32) -> FitAnswer {
+        let free_slots = self.free_slots();
+        let headroom = free_slots - parallelism;
+        // 最坏情况：slot 最多的 TaskManager 被占满后故障
+        let largest = self
+            .taskmanagers
+            .iter()
+            .map(|tm| tm.slots)
+            .max()
+            .unwrap_or(0);
+        FitAnswer {
+            parallelism,
+            free_slots,
+            fits: headroom >= 0,
+            headroom,
+            survives_taskmanager_loss: headroom >= largest,
+        }
+    }
+
+    /// TaskManager `id` 故障后其上的作业能否在其它 TaskManager 上恢复，`id` 不存在时返回 `None`
+    pub fn lose_taskmanager(&self, id: &str) -> Option<FailureAnswer> {
+        let lost = self.taskmanagers.iter().find(|tm| tm.id == id)?;
+        let free_slots = self
+            .taskmanagers
+            .iter()
+            .filter(|tm| tm.id != id)
+            .map(|tm| tm.free_slots)
+            .sum();
+        let affected_jobs = self
+            .jobs
+            .iter()
+            .map(|job| {
+                let taskmanagers = job.taskmanagers.as_ref()?;
+                Some(taskmanagers.contains(id).then(|| job.name.clone()))
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(|jobs| jobs.into_iter().flatten().collect());
+        Some(FailureAnswer {
+            taskmanager: id.to_string(),
+            lost_slots: lost.used_slots(),
+            free_slots,
+            recoverable: lost.used_slots() <= free_slots,
+            affected_jobs,
+        })
+    }
+}
+
+impl fmt::Display for CapacityModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} (Flink {}): {} TaskManagers, {} of {} slots free",
+            self.cluster,
+            self.overview.flink_version,
+            self.taskmanagers.len(),
+            self.free_slots(),
+            self.total_slots()
+        )?;
+        for tm in &self.taskmanagers {
+            write!(
+                f,
+                "  {}  {}/{} slots used",
+                tm.id,
+                tm.used_slots(),
+                tm.slots
+            )?;
+            if let Some(cores) = tm.cpu_cores {
+                write!(f, ", {} cores", cores)?;
+            }
+            if let Some(memory) = tm.physical_memory {
+                write!(f, ", {} MiB", memory / 1024 / 1024)?;
+            }
+            writeln!(f)?;
+        }
+        for job in &self.jobs {
+            writeln!(f, "  job {}  {} slots  {}", job.jid, job.slots, job.name)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model() -> CapacityModel {
+        let tm = |id: &str, slots, free_slots| TaskManagerCapacity {
+            id: id.to_string(),
+            slots,
+            free_slots,
+            cpu_cores: None,
+            physical_memory: None,
+        };
+        CapacityModel {
+            cluster: "test".to_string(),
+            overview: ClusterOverview::default(),
+            taskmanagers: vec![tm("tm-1", 4, 0), tm("tm-2", 4, 1), tm("tm-3", 4, 4)],
+            jobs: vec![
+                JobFootprint {
+                    jid: "a".to_string(),
+                    name: "orders".to_string(),
+                    slots: 5,
+                    taskmanagers: Some(BTreeSet::from(["tm-1".to_string(), "tm-2".to_string()])),
+                },
+                JobFootprint {
+                    jid: "b".to_string(),
+                    name: "payments".to_string(),
+                    slots: 2,
+                    taskmanagers: Some(BTreeSet::from(["tm-2".to_string()])),
+                },
+            ],
+            with_placement: true,
+        }
+    }
+
+    #[test]
+    fn test_fit() {
+        let model = model();
+        assert_eq!(model.free_slots(), 5);
+        let answer = model.fit(1);
+        assert!(answer.fits);
+        assert!(answer.survives_taskmanager_loss);
+        let answer = model.fit(3);
+        assert!(answer.fits);
+        assert!(!answer.survives_taskmanager_loss);
+        assert!(!model.fit(6).fits);
+    }
+
+    #[test]
+    fn test_lose_taskmanager() {
+        let model = model();
+        let answer = model.lose_taskmanager("tm-1").unwrap();
+        assert_eq!(answer.lost_slots, 4);
+        assert_eq!(answer.free_slots, 5);
+        assert!(answer.recoverable);
+        assert_eq!(answer.affected_jobs, Some(vec![String::from("orders")]));
+
+        let answer = model.lose_taskmanager("tm-3").unwrap();
+        assert_eq!(answer.lost_slots, 0);
+        assert_eq!(answer.affected_jobs, Some(Vec::new()));
+        assert!(model.lose_taskmanager("tm-9").is_none());
+
+        // 没有部署位置时不能说没有作业受影响
+        let mut model = model;
+        model.jobs[1].taskmanagers = None;
+        let answer = model.lose_taskmanager("tm-3").unwrap();
+        assert_eq!(answer.affected_jobs, None);
+        assert!(
+            answer
+                .to_string()
+                .ends_with("affected jobs: unknown (placement not fetched)")
+        );
+    }
+}
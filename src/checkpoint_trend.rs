@@ -0,0 +1,499 @@
+use crate::client::error::FlinkError;
+use crate::client::flink::FlinkClient;
+use crate::models::enums::JobState;
+use crate::models::flink::Flink;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use thiserror::Error;
+
+/// 每个作业最多保留的采样数
+const MAX_SAMPLES: usize = 2000;
+
+const HOUR_MS: f64 = 3_600_000.0;
+
+#[derive(Debug, Error)]
+pub enum TrendError {
+    #[error("failed to access {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("invalid checkpoint history {path}: {source}")]
+    Json {
+        path: String,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+/// 某个时刻最近一次完成的 checkpoint，时间单位为毫秒
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckpointSample {
+    pub at: i64,
+    pub jid: String,
+    pub checkpoint_id: i64,
+    pub state_size: i64,
+    pub checkpointed_size: i64,
+    pub duration_ms: i64,
+    /// 各顶点 barrier 对齐时间的最大值，需要额外请求，未采集时为 `None`
+    #[serde(default)]
+    pub alignment_ms: Option<i64>,
+    #[serde(default)]
+    pub timeout_ms: Option<i64>,
+}
+
+/// 本地保存的 checkpoint 采样历史，按作业名称保存以便跨版本（jid 变化）观察趋势
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CheckpointHistory {
+    #[serde(default)]
+    pub jobs: BTreeMap<String, Vec<CheckpointSample>>,
+}
+
+impl CheckpointHistory {
+    /// 文件不存在时返回空的历史
+    pub fn load(path: &str) -> Result<CheckpointHistory, TrendError> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(CheckpointHistory::default());
+            }
+            Err(source) => {
+                return Err(TrendError::Io {
+                    path: path.to_string(),
+                    source,
+                });
+            }
+        };
+        serde_json::from_str(&content).map_err(|source| TrendError::Json {
+            path: path.to_string(),
+            source,
+        })
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), TrendError> {
+        let json = serde_json::to_string(self).map_err(|source| TrendError::Json {
+            path: path.to_string(),
+            source,
+        })?;
+        let tmp = format!("{}.tmp", path);
+        fs::write(&tmp, json)
+            .and_then(|_| fs::rename(&tmp, path))
+            .map_err(|source| TrendError::Io {
+                path: path.to_string(),
+                source,
+            })
+    }
+
+    /// 同一个 checkpoint 只记录一次，返回是否为新采样
+    pub fn add(&mut self, name: &str, sample: CheckpointSample) -> bool {
+        let samples = self.jobs.entry(name.to_string()).or_default();
+        let duplicate = samples.last().is_some_and(|last| {
+            last.jid == sample.jid && last.checkpoint_id == sample.checkpoint_id
+        });
+        if duplicate {
+            return false;
+        }
+        samples.push(sample);
+        if samples.len() > MAX_SAMPLES {
+            samples.drain(..samples.len() - MAX_SAMPLES);
+        }
+        true
+    }
+}
+
+/// 采集作业最近一次完成的 checkpoint，还没有完成过 checkpoint 时返回 `None`
+pub fn sample(
+    client: &FlinkClient,
+    job: &Flink,
+    with_alignment: bool,
+    now: i64,
+) -> Result<Option<CheckpointSample>, FlinkError> {
+    let stats = client.job_checkpoints(&job.jid)?;
+    let Some(latest) = stats.latest.completed else {
+        return Ok(None);
+    };
+    // 超时时间和对齐时间只是辅助信息，取不到时忽略
+    let timeout_ms = client
+        .checkpoint_config(&job.jid)
+        .ok()
+        .map(|config| config.timeout)
+        .filter(|timeout| *timeout > 0);
+    let alignment_ms = if with_alignment {
+        client
+            .checkpoint_detail(&job.jid, latest.id)
+            .ok()
+            .and_then(|detail| {
+                detail
+                    .tasks
+                    .keys()
+                    .filter_map(|vertex| {
+                        client.checkpoint_subtasks(&job.jid, latest.id, vertex).ok()
+                    })
+                    .map(|stats| stats.summary.alignment.duration.max)
+                    .max()
+            })
+    } else {
+        None
+    };
+    Ok(Some(CheckpointSample {
+        at: now,
+        jid: job.jid.clone(),
+        checkpoint_id: latest.id,
+        state_size: latest.state_size,
+        checkpointed_size: latest.checkpointed_size,
+        duration_ms: latest.end_to_end_duration,
+        alignment_ms,
+        timeout_ms,
+    }))
+}
+
+/// 一轮采集的结果
+#[derive(Debug, Default)]
+pub struct SampleRound {
+    /// 新增的采样数
+    pub added: usize,
+    /// 采集失败的作业名称和错误，不影响其它作业
+    pub errors: Vec<(String, FlinkError)>,
+}
+
+/// 采集所有 RUNNING 作业；只有作业列表取不到时返回错误
+pub fn sample_all(
+    client: &FlinkClient,
+    history: &mut CheckpointHistory,
+    with_alignment: bool,
+    now: i64,
+) -> Result<SampleRound, FlinkError> {
+    let mut round = SampleRound::default();
+    for job in client.jobs_overview()? {
+        if job.job_state() != JobState::Running {
+            continue;
+        }
+        match sample(client, &job, with_alignment, now) {
+            Ok(Some(sample)) => {
+                if history.add(&job.name, sample) {
+                    round.added += 1;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => round.errors.push((job.name.clone(), e)),
+        }
+    }
+    Ok(round)
+}
+
+/// 趋势判断的阈值
+#[derive(Debug, Clone)]
+pub struct TrendConfig {
+    /// 少于该采样数时不做趋势判断
+    pub min_samples: usize,
+    /// 线性拟合的决定系数（R²）低于该值时认为没有明显趋势
+    pub min_r2: f64,
+    /// 拟合的状态大小在采样期间增长超过该比例时报告
+    pub growth_ratio: f64,
+    /// 最近几次 checkpoint 的耗时超过超时时间的该比例时报告
+    pub timeout_ratio: f64,
+    /// 拟合的对齐时间增长到最初的该倍数以上时报告
+    pub alignment_ratio: f64,
+    /// 对齐时间低于该值时不报告
+    pub min_alignment_ms: i64,
+}
+
+impl Default for TrendConfig {
+    fn default() -> Self {
+        TrendConfig {
+            min_samples: 6,
+            min_r2: 0.8,
+            growth_ratio: 0.2,
+            timeout_ratio: 0.8,
+            alignment_ratio: 2.0,
+            min_alignment_ms: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    StateGrowth,
+    DurationNearTimeout,
+    AlignmentIncreasing,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Warning,
+    Critical,
+}
+
+/// 可以直接序列化为 JSON 交给告警系统的发现
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub job: String,
+    pub kind: FindingKind,
+    pub severity: Severity,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct JobTrend {
+    pub job: String,
+    pub samples: usize,
+    pub latest: Option<CheckpointSample>,
+    /// 拟合的状态增长速度（字节/小时）
+    pub state_growth_per_hour: Option<f64>,
+    pub findings: Vec<Finding>,
+}
+
+/// 最小二乘拟合 `y = a + b * x`，返回 `(a, b, R²)`
+fn linear_fit(points: &[(f64, f64)]) -> Option<(f64, f64, f64)> {
+    let n = points.len() as f64;
+    if points.len() < 2 {
+        return None;
+    }
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    let sxy: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    let syy: f64 = points.iter().map(|p| (p.1 - mean_y).powi(2)).sum();
+    if sxx == 0.0 {
+        return None;
+    }
+    let slope = sxy / sxx;
+    let intercept = mean_y - slope * mean_x;
+    let r2 = if syy == 0.0 {
+        0.0
+    } else {
+        sxy * sxy / (sxx * syy)
+    };
+    Some((intercept, slope, r2))
+}
+
+pub fn analyze(job: &str, samples: &[CheckpointSample], config: &TrendConfig) -> JobTrend {
+    let mut findings = Vec::new();
+    let mut finding = |kind, severity, message: String| {
+        findings.push(Finding {
+            job: job.to_string(),
+            kind,
+            severity,
+            message,
+        })
+    };
+    let start = samples.first().map_or(0, |s| s.at);
+    let hours = |s: &CheckpointSample| (s.at - start) as f64 / HOUR_MS;
+
+    let mut state_growth_per_hour = None;
+    if samples.len() >= config.min_samples {
+        let points: Vec<(f64, f64)> = samples
+            .iter()
+            .map(|s| (hours(s), s.state_size as f64))
+            .collect();
+        if let Some((intercept, slope, r2)) = linear_fit(&points) {
+            state_growth_per_hour = Some(slope);
+            let span = points.last().map_or(0.0, |p| p.0);
+            let growth = slope * span / intercept.max(1.0);
+            if slope > 0.0 && r2 >= config.min_r2 && growth >= config.growth_ratio {
+                finding(
+                    FindingKind::StateGrowth,
+                    if growth >= 1.0 {
+                        Severity::Critical
+                    } else {
+                        Severity::Warning
+                    },
+                    format!(
+                        "state grew {:.0}% in {:.1}h ({}/h, R² {:.2})",
+                        growth * 100.0,
+                        span,
+                        format_bytes(slope as i64),
+                        r2
+                    ),
+                );
+            }
+        }
+    }
+
+    let recent = &samples[samples.len().saturating_sub(3)..];
+    if let Some(timeout) = recent.iter().rev().find_map(|s| s.timeout_ms)
+        && let Some(duration) = recent.iter().map(|s| s.duration_ms).max()
+    {
+        let ratio = duration as f64 / timeout as f64;
+        if ratio >= config.timeout_ratio {
+            finding(
+                FindingKind::DurationNearTimeout,
+                if ratio >= 0.95 {
+                    Severity::Critical
+                } else {
+                    Severity::Warning
+                },
+                format!(
+                    "checkpoint took {}ms, {:.0}% of the {}ms timeout",
+                    duration,
+                    ratio * 100.0,
+                    timeout
+                ),
+            );
+        }
+    }
+
+    let alignment: Vec<(f64, f64)> = samples
+        .iter()
+        .filter_map(|s| s.alignment_ms.map(|a| (hours(s), a as f64)))
+        .collect();
+    if alignment.len() >= config.min_samples
+        && let Some((intercept, slope, r2)) = linear_fit(&alignment)
+    {
+        let first = intercept + slope * alignment[0].0;
+        let last = intercept + slope * alignment[alignment.len() - 1].0;
+        if slope > 0.0
+            && r2 >= config.min_r2
+            && last >= config.min_alignment_ms as f64
+            && last >= first.max(1.0) * config.alignment_ratio
+        {
+            finding(
+                FindingKind::AlignmentIncreasing,
+                Severity::Warning,
+                format!(
+                    "alignment time rose from {:.0}ms to {:.0}ms (R² {:.2})",
+                    first.max(0.0),
+                    last,
+                    r2
+                ),
+            );
+        }
+    }
+
+    JobTrend {
+        job: job.to_string(),
+        samples: samples.len(),
+        latest: samples.last().cloned(),
+        state_growth_per_hour,
+        findings,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TrendReport {
+    pub jobs: Vec<JobTrend>,
+}
+
+impl TrendReport {
+    pub fn new(history: &CheckpointHistory, config: &TrendConfig) -> TrendReport {
+        TrendReport {
+            jobs: history
+                .jobs
+                .iter()
+                .map(|(name, samples)| analyze(name, samples, config))
+                .collect(),
+        }
+    }
+
+    pub fn findings(&self) -> Vec<&Finding> {
+        self.jobs.iter().flat_map(|job| &job.findings).collect()
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for job in &self.jobs {
+            let _ = writeln!(out, "{} ({} samples)", job.job, job.samples);
+            if let Some(latest) = &job.latest {
+                let _ = writeln!(
+                    out,
+                    "  latest checkpoint {}: state {}, {}ms{}",
+                    latest.checkpoint_id,
+                    format_bytes(latest.state_size),
+                    latest.duration_ms,
+                    latest
+                        .alignment_ms
+                        .map_or(String::new(), |a| format!(", alignment {}ms", a))
+                );
+            }
+            if let Some(growth) = job.state_growth_per_hour {
+                let _ = writeln!(out, "  state trend: {}/h", format_bytes(growth as i64));
+            }
+            for finding in &job.findings {
+                let _ = writeln!(out, "  [{:?}] {}", finding.severity, finding.message);
+            }
+        }
+        out
+    }
+}
+
+/// 按 1024 进制格式化字节数，例如 `1.5 MiB`；负数（下降的趋势）同样适用
+pub fn format_bytes(bytes: i64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value.abs() >= 1024.0 && unit < units.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, units[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(
+        state: impl Fn(i64) -> i64,
+        alignment: impl Fn(i64) -> i64,
+    ) -> Vec<CheckpointSample> {
+        (0..10)
+            .map(|i| CheckpointSample {
+                at: i * 600_000,
+                jid: "j1".to_string(),
+                checkpoint_id: i,
+                state_size: state(i),
+                checkpointed_size: state(i),
+                duration_ms: 1000 + i * 100,
+                alignment_ms: Some(alignment(i)),
+                timeout_ms: Some(2000),
+            })
+            .collect()
+    }
+
+    fn kinds(trend: &JobTrend) -> Vec<FindingKind> {
+        trend.findings.iter().map(|f| f.kind).collect()
+    }
+
+    #[test]
+    fn test_analyze() {
+        let config = TrendConfig::default();
+        let growing = analyze(
+            "orders",
+            &samples(|i| 1_000_000 + i * 200_000, |i| 500 + i * 300),
+            &config,
+        );
+        assert_eq!(
+            kinds(&growing),
+            vec![
+                FindingKind::StateGrowth,
+                FindingKind::DurationNearTimeout,
+                FindingKind::AlignmentIncreasing
+            ]
+        );
+        assert_eq!(growing.findings[0].severity, Severity::Critical);
+        assert_eq!(growing.findings[1].severity, Severity::Critical);
+        assert!((growing.state_growth_per_hour.unwrap() - 1_200_000.0).abs() < 1.0);
+
+        // 状态大小上下波动、对齐时间很短时不报告
+        let stable = analyze(
+            "payments",
+            &samples(|i| 1_000_000 + (i % 2) * 50_000, |i| 10 + i),
+            &config,
+        );
+        assert_eq!(kinds(&stable), vec![FindingKind::DurationNearTimeout]);
+    }
+
+    #[test]
+    fn test_history_skips_duplicates() {
+        let mut history = CheckpointHistory::default();
+        let sample = samples(|_| 1, |_| 1).remove(0);
+        assert!(history.add("orders", sample.clone()));
+        assert!(!history.add("orders", sample));
+        assert_eq!(history.jobs["orders"].len(), 1);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod error;
+pub mod flink;
+pub mod history;
+pub mod sql_gateway;
@@ -0,0 +1,50 @@
+use crate::config::ConfigError;
+use thiserror::Error;
+
+/// 访问 Flink REST 接口时可能出现的错误
+#[derive(Debug, Error)]
+pub enum FlinkError {
+    #[error("request to {url} failed: {source}")]
+    Http {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("{url} returned HTTP {status}: {body}")]
+    Status {
+        url: String,
+        status: u16,
+        body: String,
+    },
+    #[error("failed to decode response from {url}: {source}")]
+    Decode {
+        url: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("{url} was still not ready after {secs}s")]
+    NotReady { url: String, secs: u64 },
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+}
+
+impl FlinkError {
+    /// 是否值得重试：网络错误和 5xx
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            FlinkError::Http { .. } => true,
+            FlinkError::Status { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, FlinkError::Status { status: 404, .. })
+    }
+}
@@ -0,0 +1,378 @@
+use crate::client::error::FlinkError;
+use crate::config::{ConfigLoader, LoadedConfig, Secret};
+use crate::lint::LintRules;
+use crate::models::cluster::{
+    ClusterOverview, TaskManagerInfo, TaskManagerList, VertexTaskManager, VertexTaskManagers,
+};
+use crate::models::flink::{
+    AsynchronousOperation, CheckpointConfigInfo, CheckpointDetail, CheckpointStats, ConfigEntry,
+    Flink, JarList, JarRunRequest, JarRunResponse, JarUploadResponse, JobConfig, JobDetail,
+    JobExceptions, JobsOverview, SubtaskCheckpointStats, TriggerResponse,
+};
+use crate::models::plan::{BackPressureInfo, JobPlan, JobPlanResponse};
+use crate::naming::NamingConfig;
+use crate::storm::StormConfig;
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use reqwest::Method;
+use reqwest::blocking::multipart::Form;
+use reqwest::blocking::{Client, RequestBuilder};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::thread::sleep;
+use std::time::Duration;
+
+/// 单个 Flink 集群的连接配置，对应 config.toml 中的 `[[flink.clusters]]`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ClusterConfig {
+    pub name: String,
+    pub url: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    /// 可以写成 `${env:FLINK_PASSWORD}`、`${file:...}` 或 `enc:...`，见 [`crate::config::SecretResolver`]
+    #[serde(default)]
+    pub password: Option<Secret>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default)]
+    pub retries: u32,
+    /// 对应的 History Server 地址，已归档的作业从这里查询
+    #[serde(default)]
+    pub history_url: Option<String>,
+    /// 对应的 SQL Gateway 地址
+    #[serde(default)]
+    pub sql_gateway_url: Option<String>,
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct FlinkConfig {
+    #[serde(default)]
+    pub clusters: Vec<ClusterConfig>,
+    #[serde(default)]
+    pub naming: NamingConfig,
+    #[serde(default)]
+    pub storm: StormConfig,
+    #[serde(default)]
+    pub lint: LintRules,
+}
+
+impl FlinkConfig {
+    /// 按分层配置读取 `[flink]`：系统和用户文件、`file_name`、profile 文件和 `APP_FLINK__` 环境变量，
+    /// 见 [`ConfigLoader`]；格式按扩展名判断，见 [`crate::config::Format`]
+    pub fn load(file_name: &str) -> Result<FlinkConfig, FlinkError> {
+        FlinkConfig::from_loaded(&ConfigLoader::new(file_name).load()?)
+    }
+
+    /// 从已合并的配置中读取 `[flink]`，密钥引用在这里解析，`--set flink.…` 等覆盖同样生效
+    pub fn from_loaded(loaded: &LoadedConfig) -> Result<FlinkConfig, FlinkError> {
+        Ok(loaded.section("flink")?)
+    }
+}
+
+pub fn basic_auth(username: &str, password: &str) -> String {
+    let authorization = format!(r#"{}:{}"#, username, password);
+    let mut result = String::from("Basic ");
+    result.push_str(BASE64_STANDARD.encode(authorization).as_str());
+    result
+}
+
+/// Flink JobManager REST 客户端（阻塞）
+pub struct FlinkClient {
+    cluster: ClusterConfig,
+    http: Client,
+}
+
+impl FlinkClient {
+    pub fn new(cluster: ClusterConfig) -> Result<FlinkClient, FlinkError> {
+        let http = Client::builder()
+            .timeout(Duration::from_secs(cluster.timeout_secs))
+            .build()
+            .map_err(|source| FlinkError::Http {
+                url: cluster.url.clone(),
+                source,
+            })?;
+        Ok(FlinkClient { cluster, http })
+    }
+
+    pub fn cluster(&self) -> &ClusterConfig {
+        &self.cluster
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!(
+            "{}/{}",
+            self.cluster.url.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        )
+    }
+
+    /// 发送请求并返回响应文本，幂等请求（GET 等）的网络错误和 5xx 按 `retries` 重试
+    pub fn request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&serde_json::Value>,
+    ) -> Result<String, FlinkError> {
+        let (_, body) = self.execute(method, path, |builder| match body {
+            Some(body) => Ok(builder.json(body)),
+            None => Ok(builder),
+        })?;
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+
+    /// `prepare` 在每次尝试前补充请求体，便于重试时重新构造 multipart 等不可复用的内容。
+    /// POST/PATCH 不重试：超时时 JobManager 可能已经接受了请求，重试会重复提交作业或触发 savepoint
+    fn execute(
+        &self,
+        method: Method,
+        path: &str,
+        prepare: impl Fn(RequestBuilder) -> Result<RequestBuilder, FlinkError>,
+    ) -> Result<(u16, Vec<u8>), FlinkError> {
+        let url = self.url(path);
+        let retries = if method.is_idempotent() {
+            self.cluster.retries
+        } else {
+            0
+        };
+        let mut attempt = 0;
+        loop {
+            match self.send_once(method.clone(), &url, &prepare) {
+                Err(e) if e.is_retryable() && attempt < retries => {
+                    attempt += 1;
+                    sleep(Duration::from_millis(500 * 2u64.pow(attempt - 1)));
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn send_once(
+        &self,
+        method: Method,
+        url: &str,
+        prepare: &impl Fn(RequestBuilder) -> Result<RequestBuilder, FlinkError>,
+    ) -> Result<(u16, Vec<u8>), FlinkError> {
+        let mut builder = self.http.request(method, url);
+        if let Some(username) = &self.cluster.username {
+            let password = self.cluster.password.as_ref().map(|p| p.expose());
+            let password = password.unwrap_or_default();
+            builder = builder.header("authorization", basic_auth(username, password));
+        }
+        let builder = prepare(builder)?;
+        let http_error = |source| FlinkError::Http {
+            url: url.to_string(),
+            source,
+        };
+        let response = builder.send().map_err(http_error)?;
+        let status = response.status();
+        let body = response.bytes().map_err(http_error)?.to_vec();
+        if !status.is_success() {
+            return Err(FlinkError::Status {
+                url: url.to_string(),
+                status: status.as_u16(),
+                body: String::from_utf8_lossy(&body).into_owned(),
+            });
+        }
+        Ok((status.as_u16(), body))
+    }
+
+    /// 发送请求并把响应解析为 `T`
+    pub fn send_json<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&serde_json::Value>,
+    ) -> Result<T, FlinkError> {
+        let response = self.request(method, path, body)?;
+        serde_json::from_str(&response).map_err(|source| FlinkError::Decode {
+            url: self.url(path),
+            source,
+        })
+    }
+
+    pub fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T, FlinkError> {
+        self.send_json(Method::GET, path, None)
+    }
+
+    pub fn post_json<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &serde_json::Value,
+    ) -> Result<T, FlinkError> {
+        self.send_json(Method::POST, path, Some(body))
+    }
+
+    pub fn jobs_overview(&self) -> Result<Vec<Flink>, FlinkError> {
+        let overview: JobsOverview = self.get_json("/jobs/overview")?;
+        Ok(overview.jobs)
+    }
+
+    pub fn job_detail(&self, jid: &str) -> Result<JobDetail, FlinkError> {
+        self.get_json(&format!("/jobs/{}", jid))
+    }
+
+    pub fn job_checkpoints(&self, jid: &str) -> Result<CheckpointStats, FlinkError> {
+        self.get_json(&format!("/jobs/{}/checkpoints", jid))
+    }
+
+    pub fn checkpoint_config(&self, jid: &str) -> Result<CheckpointConfigInfo, FlinkError> {
+        self.get_json(&format!("/jobs/{}/checkpoints/config", jid))
+    }
+
+    pub fn checkpoint_detail(&self, jid: &str, id: i64) -> Result<CheckpointDetail, FlinkError> {
+        self.get_json(&format!("/jobs/{}/checkpoints/details/{}", jid, id))
+    }
+
+    pub fn checkpoint_subtasks(
+        &self,
+        jid: &str,
+        id: i64,
+        vertex_id: &str,
+    ) -> Result<SubtaskCheckpointStats, FlinkError> {
+        self.get_json(&format!(
+            "/jobs/{}/checkpoints/details/{}/subtasks/{}",
+            jid, id, vertex_id
+        ))
+    }
+
+    pub fn job_exceptions(&self, jid: &str) -> Result<JobExceptions, FlinkError> {
+        self.get_json(&format!("/jobs/{}/exceptions", jid))
+    }
+
+    pub fn job_config(&self, jid: &str) -> Result<JobConfig, FlinkError> {
+        self.get_json(&format!("/jobs/{}/config", jid))
+    }
+
+    /// JobManager 的集群配置
+    pub fn jobmanager_config(&self) -> Result<Vec<ConfigEntry>, FlinkError> {
+        self.get_json("/jobmanager/config")
+    }
+
+    /// 作业提交时生效的完整配置（Flink 1.17+），包含 `pipeline.jars` 等
+    pub fn job_jobmanager_config(&self, jid: &str) -> Result<Vec<ConfigEntry>, FlinkError> {
+        self.get_json(&format!("/jobs/{}/jobmanager/config", jid))
+    }
+
+    pub fn list_jars(&self) -> Result<JarList, FlinkError> {
+        self.get_json("/jars")
+    }
+
+    /// 上传本地 jar，返回服务器分配的 jar id
+    pub fn upload_jar(&self, path: &str) -> Result<String, FlinkError> {
+        let (_, response) = self.execute(Method::POST, "/jars/upload", |builder| {
+            let form = Form::new()
+                .file("jarfile", path)
+                .map_err(|source| FlinkError::Io {
+                    path: path.to_string(),
+                    source,
+                })?;
+            Ok(builder.multipart(form))
+        })?;
+        let upload: JarUploadResponse =
+            serde_json::from_slice(&response).map_err(|source| FlinkError::Decode {
+                url: self.url("/jars/upload"),
+                source,
+            })?;
+        let jar_id = upload.filename.rsplit('/').next().unwrap_or_default();
+        Ok(jar_id.to_string())
+    }
+
+    /// 运行已上传的 jar，返回新作业的 jid
+    pub fn run_jar(&self, jar_id: &str, request: &JarRunRequest) -> Result<String, FlinkError> {
+        let body = serde_json::to_value(request).unwrap_or_default();
+        let response: JarRunResponse = self.post_json(&format!("/jars/{}/run", jar_id), &body)?;
+        Ok(response.jobid)
+    }
+
+    /// 触发 stop-with-savepoint，返回用于查询进度的 trigger id
+    pub fn stop_with_savepoint(
+        &self,
+        jid: &str,
+        target_directory: Option<&str>,
+        drain: bool,
+    ) -> Result<String, FlinkError> {
+        let body = serde_json::json!({
+            "targetDirectory": target_directory,
+            "drain": drain,
+        });
+        let response: TriggerResponse = self.post_json(&format!("/jobs/{}/stop", jid), &body)?;
+        Ok(response.request_id)
+    }
+
+    pub fn savepoint_status(
+        &self,
+        jid: &str,
+        trigger_id: &str,
+    ) -> Result<AsynchronousOperation, FlinkError> {
+        self.get_json(&format!("/jobs/{}/savepoints/{}", jid, trigger_id))
+    }
+
+    pub fn cancel_job(&self, jid: &str) -> Result<(), FlinkError> {
+        self.request(Method::PATCH, &format!("/jobs/{}?mode=cancel", jid), None)?;
+        Ok(())
+    }
+
+    pub fn job_plan(&self, jid: &str) -> Result<JobPlan, FlinkError> {
+        let response: JobPlanResponse = self.get_json(&format!("/jobs/{}/plan", jid))?;
+        Ok(response.plan)
+    }
+
+    pub fn vertex_backpressure(
+        &self,
+        jid: &str,
+        vertex_id: &str,
+    ) -> Result<BackPressureInfo, FlinkError> {
+        self.get_json(&format!(
+            "/jobs/{}/vertices/{}/backpressure",
+            jid, vertex_id
+        ))
+    }
+
+    /// 读取 `[start, end)` 字节，同时返回服务端是否支持 Range；
+    /// 不支持（没有返回 206）时返回的是完整内容，由调用方在本地截取。
+    /// 区间为空时不发请求，`bytes=0-0` 这样的请求在空文件上会得到 416
+    pub fn get_bytes_range(
+        &self,
+        path: &str,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<(Vec<u8>, bool), FlinkError> {
+        if end.is_some_and(|end| start >= end) {
+            return Ok((Vec::new(), true));
+        }
+        let range = match end {
+            Some(end) => format!("bytes={}-{}", start, end.saturating_sub(1)),
+            None => format!("bytes={}-", start),
+        };
+        let (status, body) = self.execute(Method::GET, path, |builder| {
+            Ok(builder.header("range", &range))
+        })?;
+        Ok((body, status == 206))
+    }
+
+    pub fn taskmanagers(&self) -> Result<Vec<TaskManagerInfo>, FlinkError> {
+        let list: TaskManagerList = self.get_json("/taskmanagers")?;
+        Ok(list.taskmanagers)
+    }
+
+    pub fn overview(&self) -> Result<ClusterOverview, FlinkError> {
+        self.get_json("/overview")
+    }
+
+    /// 顶点的各个 subtask 所在的 TaskManager
+    pub fn vertex_taskmanagers(
+        &self,
+        jid: &str,
+        vertex_id: &str,
+    ) -> Result<Vec<VertexTaskManager>, FlinkError> {
+        let list: VertexTaskManagers = self.get_json(&format!(
+            "/jobs/{}/vertices/{}/taskmanagers",
+            jid, vertex_id
+        ))?;
+        Ok(list.taskmanagers)
+    }
+}
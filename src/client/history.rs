@@ -0,0 +1,130 @@
+use crate::client::error::FlinkError;
+use crate::client::flink::{ClusterConfig, FlinkClient};
+use crate::models::flink::{
+    CheckpointStats, Flink, JobConfig, JobDetail, JobExceptions, JobsOverview,
+};
+
+/// Flink History Server 客户端，返回的作业与 JobManager 使用同一套模型
+///
+/// History Server 只提供只读的归档数据，认证、超时和重试沿用 [`FlinkClient`]。
+pub struct HistoryClient {
+    inner: FlinkClient,
+}
+
+impl HistoryClient {
+    pub fn new(cluster: ClusterConfig) -> Result<HistoryClient, FlinkError> {
+        Ok(HistoryClient {
+            inner: FlinkClient::new(cluster)?,
+        })
+    }
+
+    /// 根据集群配置中的 `history_url` 创建客户端，未配置时返回 `None`
+    pub fn for_cluster(cluster: &ClusterConfig) -> Option<Result<HistoryClient, FlinkError>> {
+        let url = cluster.history_url.clone()?;
+        let config = ClusterConfig {
+            name: format!("{}-history", cluster.name),
+            url,
+            history_url: None,
+            sql_gateway_url: None,
+            ..cluster.clone()
+        };
+        Some(HistoryClient::new(config))
+    }
+
+    pub fn cluster(&self) -> &ClusterConfig {
+        self.inner.cluster()
+    }
+
+    pub fn archived_jobs(&self) -> Result<Vec<Flink>, FlinkError> {
+        let overview: JobsOverview = self.inner.get_json("/jobs/overview")?;
+        Ok(overview.jobs)
+    }
+
+    /// 按 jid 查找归档作业，不存在时返回 `None`
+    pub fn job(&self, jid: &str) -> Result<Option<Flink>, FlinkError> {
+        Ok(self.archived_jobs()?.into_iter().find(|job| job.jid == jid))
+    }
+
+    pub fn job_detail(&self, jid: &str) -> Result<JobDetail, FlinkError> {
+        self.inner.job_detail(jid)
+    }
+
+    pub fn job_checkpoints(&self, jid: &str) -> Result<CheckpointStats, FlinkError> {
+        self.inner.job_checkpoints(jid)
+    }
+
+    pub fn job_exceptions(&self, jid: &str) -> Result<JobExceptions, FlinkError> {
+        self.inner.job_exceptions(jid)
+    }
+
+    pub fn job_config(&self, jid: &str) -> Result<JobConfig, FlinkError> {
+        self.inner.job_config(jid)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JobSource {
+    Live,
+    History,
+}
+
+/// 先在 JobManager 上查找作业，找不到或 JobManager 不可用时再查 History Server；
+/// History Server 也没有找到时才返回 JobManager 的错误
+pub fn find_job(
+    live: &FlinkClient,
+    history: Option<&HistoryClient>,
+    jid: &str,
+) -> Result<Option<(JobSource, Flink)>, FlinkError> {
+    let live_error = match live.jobs_overview() {
+        Ok(jobs) => {
+            if let Some(job) = jobs.into_iter().find(|job| job.jid == jid) {
+                return Ok(Some((JobSource::Live, job)));
+            }
+            None
+        }
+        Err(e) => Some(e),
+    };
+    let found = match history {
+        Some(history) => match history.job(jid) {
+            Ok(job) => job.map(|job| (JobSource::History, job)),
+            // History Server 尚未归档任何作业时 overview 也可能是 404
+            Err(e) if e.is_not_found() => None,
+            Err(e) => return Err(live_error.unwrap_or(e)),
+        },
+        None => None,
+    };
+    match (found, live_error) {
+        (Some(found), _) => Ok(Some(found)),
+        (None, Some(e)) => Err(e),
+        (None, None) => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_cluster() {
+        let cluster: ClusterConfig = toml::from_str(
+            r#"
+            name = "prod"
+            url = "http://jobmanager:8081"
+            username = "admin"
+            retries = 2
+            "#,
+        )
+        .unwrap();
+        assert!(HistoryClient::for_cluster(&cluster).is_none());
+
+        let cluster = ClusterConfig {
+            history_url: Some("http://history:8082".to_string()),
+            ..cluster
+        };
+        let history = HistoryClient::for_cluster(&cluster).unwrap().unwrap();
+        assert_eq!(history.cluster().name, "prod-history");
+        assert_eq!(history.cluster().url, "http://history:8082");
+        assert_eq!(history.cluster().username.as_deref(), Some("admin"));
+        assert_eq!(history.cluster().retries, 2);
+    }
+}
@@ -0,0 +1,325 @@
+use crate::client::error::FlinkError;
+use crate::client::flink::{ClusterConfig, FlinkClient};
+use crate::models::sql_gateway::{
+    ColumnInfo, ExecuteStatementResponse, FetchResultsResponse, OpenSessionResponse,
+    OperationStatus, ResultType, RowData, RowKind, SessionConfig,
+};
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use std::collections::{BTreeMap, VecDeque};
+use std::marker::PhantomData;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Flink SQL Gateway REST 客户端（v2 接口，Flink 1.17+；[`SqlGatewayClient::set_property`] 需要 1.18+）
+///
+/// 认证、超时和重试沿用 [`FlinkClient`]，地址取自集群配置的 `sql_gateway_url`。
+pub struct SqlGatewayClient {
+    inner: FlinkClient,
+    poll_interval: Duration,
+    ready_timeout: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub handle: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Operation {
+    pub session: String,
+    pub handle: String,
+}
+
+impl Operation {
+    fn path(&self, suffix: &str) -> String {
+        format!(
+            "/v2/sessions/{}/operations/{}/{}",
+            self.session, self.handle, suffix
+        )
+    }
+}
+
+/// 带变更类型的一行结果
+#[derive(Debug, Clone)]
+pub struct Row<T> {
+    pub kind: RowKind,
+    pub value: T,
+}
+
+impl SqlGatewayClient {
+    pub fn new(cluster: ClusterConfig) -> Result<SqlGatewayClient, FlinkError> {
+        Ok(SqlGatewayClient {
+            ready_timeout: Duration::from_secs(cluster.timeout_secs),
+            inner: FlinkClient::new(cluster)?,
+            poll_interval: Duration::from_millis(500),
+        })
+    }
+
+    /// 根据集群配置中的 `sql_gateway_url` 创建客户端，未配置时返回 `None`
+    pub fn for_cluster(cluster: &ClusterConfig) -> Option<Result<SqlGatewayClient, FlinkError>> {
+        let url = cluster.sql_gateway_url.clone()?;
+        let config = ClusterConfig {
+            name: format!("{}-sql-gateway", cluster.name),
+            url,
+            history_url: None,
+            sql_gateway_url: None,
+            ..cluster.clone()
+        };
+        Some(SqlGatewayClient::new(config))
+    }
+
+    /// 结果尚未就绪时的轮询间隔
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> SqlGatewayClient {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// 结果一直未就绪时最多等待多久，默认为集群的 `timeout_secs`
+    pub fn with_ready_timeout(mut self, ready_timeout: Duration) -> SqlGatewayClient {
+        self.ready_timeout = ready_timeout;
+        self
+    }
+
+    pub fn cluster(&self) -> &ClusterConfig {
+        self.inner.cluster()
+    }
+
+    pub fn open_session(
+        &self,
+        name: Option<&str>,
+        properties: &BTreeMap<String, String>,
+    ) -> Result<Session, FlinkError> {
+        let body = json!({ "sessionName": name, "properties": properties });
+        let response: OpenSessionResponse = self.inner.post_json("/v2/sessions", &body)?;
+        Ok(Session {
+            handle: response.session_handle,
+        })
+    }
+
+    pub fn close_session(&self, session: &Session) -> Result<(), FlinkError> {
+        self.inner.request(
+            Method::DELETE,
+            &format!("/v2/sessions/{}", session.handle),
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub fn session_properties(
+        &self,
+        session: &Session,
+    ) -> Result<BTreeMap<String, String>, FlinkError> {
+        let config: SessionConfig = self
+            .inner
+            .get_json(&format!("/v2/sessions/{}", session.handle))?;
+        Ok(config.properties)
+    }
+
+    /// 相当于在会话中执行 `SET 'key' = 'value'`，`configure-session` 接口从 Flink 1.18 开始提供
+    pub fn set_property(
+        &self,
+        session: &Session,
+        key: &str,
+        value: &str,
+    ) -> Result<(), FlinkError> {
+        let statement = format!("SET '{}' = '{}'", escape(key), escape(value));
+        self.inner.request(
+            Method::POST,
+            &format!("/v2/sessions/{}/configure-session", session.handle),
+            Some(&json!({ "statement": statement })),
+        )?;
+        Ok(())
+    }
+
+    pub fn execute(&self, session: &Session, statement: &str) -> Result<Operation, FlinkError> {
+        let response: ExecuteStatementResponse = self.inner.post_json(
+            &format!("/v2/sessions/{}/statements", session.handle),
+            &json!({ "statement": statement }),
+        )?;
+        Ok(Operation {
+            session: session.handle.clone(),
+            handle: response.operation_handle,
+        })
+    }
+
+    pub fn operation_status(&self, operation: &Operation) -> Result<String, FlinkError> {
+        let status: OperationStatus = self.inner.get_json(&operation.path("status"))?;
+        Ok(status.status)
+    }
+
+    pub fn cancel(&self, operation: &Operation) -> Result<String, FlinkError> {
+        let status: OperationStatus =
+            self.inner
+                .send_json(Method::POST, &operation.path("cancel"), None)?;
+        Ok(status.status)
+    }
+
+    pub fn close_operation(&self, operation: &Operation) -> Result<(), FlinkError> {
+        self.inner
+            .request(Method::DELETE, &operation.path("close"), None)?;
+        Ok(())
+    }
+
+    /// 拉取一页结果；`uri` 为上一页的 `nextResultUri`，`None` 表示第一页
+    pub fn fetch_page(
+        &self,
+        operation: &Operation,
+        uri: Option<&str>,
+    ) -> Result<FetchResultsResponse, FlinkError> {
+        match uri {
+            Some(uri) => self.inner.get_json(uri),
+            None => self.inner.get_json(&operation.path(FIRST_PAGE)),
+        }
+    }
+
+    /// 逐行读取结果并按列名反序列化为 `T`，自动翻页并等待未就绪的结果
+    pub fn rows<T: DeserializeOwned>(&self, operation: &Operation) -> ResultRows<'_, T> {
+        ResultRows {
+            client: self,
+            operation: operation.clone(),
+            next_uri: Some(operation.path(FIRST_PAGE)),
+            columns: Vec::new(),
+            buffer: VecDeque::new(),
+            not_ready_since: None,
+            _row: PhantomData,
+        }
+    }
+}
+
+const FIRST_PAGE: &str = "result/0?rowFormat=JSON";
+
+fn escape(value: &str) -> String {
+    value.replace('\'', "''")
+}
+
+pub struct ResultRows<'a, T> {
+    client: &'a SqlGatewayClient,
+    operation: Operation,
+    next_uri: Option<String>,
+    columns: Vec<ColumnInfo>,
+    buffer: VecDeque<RowData>,
+    /// 连续收到 NOT_READY 的开始时间
+    not_ready_since: Option<Instant>,
+    _row: PhantomData<T>,
+}
+
+impl<T: DeserializeOwned> ResultRows<'_, T> {
+    pub fn columns(&self) -> &[ColumnInfo] {
+        &self.columns
+    }
+
+    fn decode(&self, row: RowData) -> Result<Row<T>, FlinkError> {
+        let value = serde_json::from_value(row.to_object(&self.columns)).map_err(|source| {
+            FlinkError::Decode {
+                url: self.client.inner.url(&self.operation.path("result")),
+                source,
+            }
+        })?;
+        Ok(Row {
+            kind: row.kind,
+            value,
+        })
+    }
+}
+
+impl<T: DeserializeOwned> Iterator for ResultRows<'_, T> {
+    type Item = Result<Row<T>, FlinkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.buffer.pop_front() {
+                return Some(self.decode(row));
+            }
+            let uri = self.next_uri.as_deref()?;
+            let page = match self.client.fetch_page(&self.operation, Some(uri)) {
+                Ok(page) => page,
+                Err(e) => {
+                    self.next_uri = None;
+                    return Some(Err(e));
+                }
+            };
+            if page.result_type == ResultType::NotReady {
+                let since = *self.not_ready_since.get_or_insert_with(Instant::now);
+                if since.elapsed() >= self.client.ready_timeout {
+                    let url = self.client.inner.url(uri);
+                    self.next_uri = None;
+                    return Some(Err(FlinkError::NotReady {
+                        url,
+                        secs: self.client.ready_timeout.as_secs(),
+                    }));
+                }
+                // 未就绪时 nextResultUri 指向同一页，没有返回时沿用当前地址
+                if let Some(uri) = page.next_result_uri {
+                    self.next_uri = Some(uri);
+                }
+                sleep(self.client.poll_interval);
+                continue;
+            }
+            self.not_ready_since = None;
+            if !page.results.columns.is_empty() {
+                self.columns = page.results.columns;
+            }
+            let empty = page.results.data.is_empty();
+            self.buffer.extend(page.results.data);
+            self.next_uri = match page.result_type {
+                ResultType::Eos => None,
+                _ => page.next_result_uri,
+            };
+            // 流式查询暂时没有新数据时返回空的 PAYLOAD 页，不等待会不停地请求
+            if empty && self.next_uri.is_some() {
+                sleep(self.client.poll_interval);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[test]
+    fn test_decode_typed_row() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Order {
+            id: i64,
+            product: String,
+        }
+
+        let page: FetchResultsResponse = serde_json::from_str(
+            r#"{
+                "resultType": "PAYLOAD",
+                "isQueryResult": true,
+                "jobID": "a1b2",
+                "resultKind": "SUCCESS_WITH_CONTENT",
+                "results": {
+                    "columns": [
+                        {"name": "id", "logicalType": {"type": "BIGINT", "nullable": false}},
+                        {"name": "product", "logicalType": {"type": "VARCHAR", "nullable": true}}
+                    ],
+                    "rowFormat": "JSON",
+                    "data": [{"kind": "UPDATE_AFTER", "fields": [7, "pen"]}]
+                },
+                "nextResultUri": "/v2/sessions/s/operations/o/result/1?rowFormat=JSON"
+            }"#,
+        )
+        .unwrap();
+        let row = &page.results.data[0];
+        let order: Order = serde_json::from_value(row.to_object(&page.results.columns)).unwrap();
+        assert_eq!(row.kind, RowKind::UpdateAfter);
+        assert_eq!(
+            order,
+            Order {
+                id: 7,
+                product: "pen".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("it's"), "it''s");
+    }
+}
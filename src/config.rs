@@ -0,0 +1,276 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use thiserror::Error;
+
+pub mod features;
+pub mod format;
+pub mod layered;
+pub mod migrate;
+pub mod schema;
+pub mod secret;
+pub mod show;
+pub mod validate;
+pub mod watch;
+
+pub use features::{FeatureFlags, Rollout, Toggle};
+pub use format::Format;
+pub use layered::{ConfigLoader, Layer, LoadedConfig, Source};
+pub use migrate::CURRENT_VERSION;
+pub use secret::{Secret, SecretError, SecretResolver};
+pub use show::Entry;
+pub use validate::ValidationIssue;
+pub use watch::{ConfigWatcher, Reload};
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error(
+        "{path}:{line}:{column}: {}{message}",
+        key.as_ref().map(|key| format!("{}: ", key)).unwrap_or_default()
+    )]
+    Invalid {
+        path: String,
+        /// 出错的配置项，例如 `app.port`，语法错误时可能无法确定
+        key: Option<String>,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// 合并后才发现的错误，`from` 为该配置项最终取值的来源
+    #[error(
+        "{}{}{message}",
+        key.as_ref().map(|key| format!("{}: ", key)).unwrap_or_default(),
+        from.as_ref().map(|from| format!("(from {}) ", from)).unwrap_or_default()
+    )]
+    Merged {
+        key: Option<String>,
+        from: Option<Source>,
+        message: String,
+    },
+    #[error("invalid override {arg:?}: {message}")]
+    Override { arg: String, message: String },
+    #[error(
+        "{key}: {}{source}",
+        from.as_ref().map(|from| format!("(from {}) ", from)).unwrap_or_default()
+    )]
+    Secret {
+        key: String,
+        from: Option<Source>,
+        #[source]
+        source: SecretError,
+    },
+    #[error("{path}: unsupported config version {version}, expected 1 to {CURRENT_VERSION}")]
+    Version { path: String, version: String },
+}
+
+/// 配置文件（config.toml、config.yaml 或 config.json）中应用自身的配置，`[flink]` 部分由 [`crate::client::flink::FlinkConfig`] 读取
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct Config {
+    pub app: ApplicationConfig,
+    pub search: SearchConfig,
+    /// 功能开关，见 [`FeatureFlags`]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub features: BTreeMap<String, Toggle>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SearchConfig {
+    /// 搜索服务的节点地址，版本 1 的 `search.address` 会迁移到这里
+    pub nodes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// 可以写成 `${env:SEARCH_PASSWORD}` 等密钥引用，见 [`SecretResolver`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<Secret>,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            nodes: vec![String::from("http://localhost:9200")],
+            username: None,
+            password: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ApplicationConfig {
+    pub port: i32,
+    pub page_size: i32,
+}
+
+impl Default for ApplicationConfig {
+    fn default() -> Self {
+        ApplicationConfig {
+            port: 3000,
+            page_size: 10,
+        }
+    }
+}
+
+impl Config {
+    pub fn load(path: &str) -> Result<Config, ConfigError> {
+        let content = fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        Config::parse(path, &content)
+    }
+
+    /// 格式由 `path` 的扩展名决定，密钥引用使用 [`SecretResolver::from_env`] 解析
+    pub fn parse(path: &str, content: &str) -> Result<Config, ConfigError> {
+        Config::parse_as(Format::for_path(path), path, content)
+    }
+
+    /// 指定格式读取，`path` 只用于错误信息
+    pub fn parse_as(format: Format, path: &str, content: &str) -> Result<Config, ConfigError> {
+        let mut table = format.parse::<Config>(path, content)?;
+        SecretResolver::from_env()
+            .resolve_table(&mut table)
+            .map_err(|(key, source)| ConfigError::Secret {
+                key,
+                from: None,
+                source,
+            })?;
+        Config::deserialize(toml::Value::Table(table)).map_err(|e| ConfigError::Merged {
+            key: None,
+            from: None,
+            message: e.message().to_string(),
+        })
+    }
+}
+
+pub fn parse_config(file_name: &str) -> Result<Config, ConfigError> {
+    Config::load(file_name)
+}
+
+fn invalid(path: &str, content: &str, source: toml::de::Error) -> ConfigError {
+    let (key, line, column) = error_location(content, &source);
+    ConfigError::Invalid {
+        path: path.to_string(),
+        key,
+        line,
+        column,
+        message: source.message().to_string(),
+    }
+}
+
+/// 错误所在的配置项、行号和列号
+fn error_location(content: &str, error: &toml::de::Error) -> (Option<String>, usize, usize) {
+    let offset = error.span().map(|span| span.start).unwrap_or(0);
+    let (line, column) = line_column(content, offset);
+    let mut key = key_at(content, line);
+    // 缺少字段时 span 指向所在的表，字段名只出现在错误信息里；
+    // 缺少顶层的表时 span 指向文件开头，那一行不是表头，不能拼上该行的键
+    if let Some(field) = error
+        .message()
+        .strip_prefix("missing field `")
+        .and_then(|rest| rest.split('`').next())
+    {
+        let header = content
+            .lines()
+            .nth(line - 1)
+            .is_some_and(|text| text.trim_start().starts_with('['));
+        key = Some(match key {
+            Some(table) if header => format!("{}.{}", table, field),
+            _ => field.to_string(),
+        });
+    }
+    (key, line, column)
+}
+
+/// 字节偏移转换为从 1 开始的行号和列号
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .map(|text| text.chars().count())
+        .unwrap_or(0)
+        + 1;
+    (line, column)
+}
+
+/// 根据出错所在的行找出完整的配置项名称：该行的键加上前面最近的表头
+fn key_at(content: &str, line: usize) -> Option<String> {
+    let mut table: Option<String> = None;
+    for text in content.lines().take(line) {
+        let text = text.trim();
+        if let Some(header) = text.strip_prefix('[') {
+            let header = header.trim_start_matches('[');
+            table = header.split(']').next().map(|name| name.trim().to_string());
+        }
+    }
+    let text = content.lines().nth(line.checked_sub(1)?)?.trim();
+    if text.starts_with('[') {
+        return table;
+    }
+    let name = text.split_once('=')?.0.trim().trim_matches('"');
+    Some(match table {
+        Some(table) => format!("{}.{}", table, name),
+        None => name.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"version = 2
+
+[app]
+port = 3000
+page_size = 10
+
+[search]
+nodes = ["http://localhost:9200"]
+"#;
+
+    #[test]
+    fn test_parse() {
+        let config = Config::parse("config.toml", CONFIG).unwrap();
+        assert_eq!(config.app.port, 3000);
+        assert_eq!(config.search.nodes, vec!["http://localhost:9200"]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let content = CONFIG.replace("page_size = 10", "page_size = \"ten\"");
+        match Config::parse("config.toml", &content) {
+            Err(ConfigError::Invalid {
+                key, line, column, ..
+            }) => {
+                assert_eq!(key.as_deref(), Some("app.page_size"));
+                assert_eq!((line, column), (5, 13));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let content = CONFIG.replace("port = 3000\n", "");
+        let error = Config::parse("config.toml", &content).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("config.toml:3:1: app.port: missing field `port`"),
+            "{}",
+            error
+        );
+
+        let content = &CONFIG[..CONFIG.find("[search]").unwrap()];
+        match Config::parse("config.toml", content) {
+            Err(ConfigError::Invalid { key, message, .. }) => {
+                assert_eq!(key.as_deref(), Some("search"));
+                assert_eq!(message, "missing field `search`");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use crate::config::Config;
+use ring::digest::{SHA256, digest};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// 按环境覆盖时没有对应环境所使用的键
+pub const DEFAULT_ENVIRONMENT: &str = "default";
+
+/// 开关的取值：`true`/`false`，或 0 到 100 的灰度百分比
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Rollout {
+    Enabled(bool),
+    Percent(u8),
+}
+
+/// `[features]` 中的一个开关：`name = true`、`name = 25`，或按环境（profile）覆盖的
+/// `name = { default = 10, staging = 100, prod = 0 }`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Toggle {
+    Fixed(Rollout),
+    PerEnvironment(BTreeMap<String, Rollout>),
+}
+
+impl Toggle {
+    /// 环境对应的取值，没有该环境时使用 `default`，都没有时关闭
+    pub fn rollout(&self, environment: Option<&str>) -> Rollout {
+        match self {
+            Toggle::Fixed(rollout) => *rollout,
+            Toggle::PerEnvironment(values) => environment
+                .and_then(|environment| values.get(environment))
+                .or_else(|| values.get(DEFAULT_ENVIRONMENT))
+                .copied()
+                .unwrap_or(Rollout::Enabled(false)),
+        }
+    }
+}
+
+impl Rollout {
+    /// 百分比灰度按开关名和 `subject` 的哈希稳定分桶，同一个 subject 的结果不随重启变化；
+    /// 没有 subject 时只有 100% 才算打开
+    pub fn includes(&self, feature: &str, subject: Option<&str>) -> bool {
+        match (*self, subject) {
+            (Rollout::Enabled(enabled), _) => enabled,
+            (Rollout::Percent(percent), _) if percent >= 100 => true,
+            (Rollout::Percent(_), None) => false,
+            (Rollout::Percent(percent), Some(subject)) => {
+                bucket(feature, subject) < u64::from(percent)
+            }
+        }
+    }
+}
+
+/// 0 到 99 的分桶
+fn bucket(feature: &str, subject: &str) -> u64 {
+    let hash = digest(&SHA256, format!("{}:{}", feature, subject).as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash.as_ref()[..8]);
+    u64::from_be_bytes(bytes) % 100
+}
+
+impl Config {
+    /// 开关是否对 `subject`（服务名、任务名等）打开，未声明的开关为关闭
+    pub fn feature_enabled(
+        &self,
+        name: &str,
+        environment: Option<&str>,
+        subject: Option<&str>,
+    ) -> bool {
+        self.features
+            .get(name)
+            .is_some_and(|toggle| toggle.rollout(environment).includes(name, subject))
+    }
+}
+
+/// 运行时查询开关的句柄，配合 [`crate::config::ConfigWatcher::features`] 跟随配置热更新
+#[derive(Debug, Clone)]
+pub struct FeatureFlags {
+    receiver: watch::Receiver<Arc<Config>>,
+    environment: Option<String>,
+}
+
+impl FeatureFlags {
+    /// `environment` 通常是 profile，用来选择按环境覆盖的取值
+    pub fn new(
+        receiver: watch::Receiver<Arc<Config>>,
+        environment: Option<String>,
+    ) -> FeatureFlags {
+        FeatureFlags {
+            receiver,
+            environment,
+        }
+    }
+
+    /// 不会更新的固定配置
+    pub fn fixed(config: Config, environment: Option<String>) -> FeatureFlags {
+        let (_, receiver) = watch::channel(Arc::new(config));
+        FeatureFlags::new(receiver, environment)
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.query(name, None)
+    }
+
+    /// 百分比灰度的开关对一部分 subject 打开
+    pub fn is_enabled_for(&self, name: &str, subject: &str) -> bool {
+        self.query(name, Some(subject))
+    }
+
+    fn query(&self, name: &str, subject: Option<&str>) -> bool {
+        self.receiver
+            .borrow()
+            .feature_enabled(name, self.environment.as_deref(), subject)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_features() {
+        let config = Config::parse(
+            "config.toml",
+            r#"
+            version = 2
+
+            [app]
+            port = 3000
+            page_size = 10
+
+            [search]
+            nodes = ["http://localhost:9200"]
+
+            [features]
+            new_alert_rules = true
+            search_indexing = 25
+            storm_remediation = { default = 0, staging = 100 }
+            "#,
+        )
+        .unwrap();
+        let (sender, receiver) = watch::channel(Arc::new(config.clone()));
+        let prod = FeatureFlags::new(receiver.clone(), Some(String::from("prod")));
+        let staging = FeatureFlags::new(receiver, Some(String::from("staging")));
+
+        assert!(prod.is_enabled("new_alert_rules"));
+        assert!(!prod.is_enabled("unknown"));
+        assert!(!prod.is_enabled("search_indexing"));
+        let enabled = (0..1000)
+            .filter(|i| prod.is_enabled_for("search_indexing", &format!("service-{}", i)))
+            .count();
+        assert!((200..300).contains(&enabled), "{}", enabled);
+        assert!(!prod.is_enabled("storm_remediation"));
+        assert!(staging.is_enabled("storm_remediation"));
+
+        let mut reloaded = config;
+        reloaded.features.insert(
+            String::from("new_alert_rules"),
+            Toggle::Fixed(Rollout::Enabled(false)),
+        );
+        sender.send_replace(Arc::new(reloaded));
+        assert!(!prod.is_enabled("new_alert_rules"));
+    }
+}
@@ -0,0 +1,320 @@
+use crate::config::migrate::migrate;
+use crate::config::{ConfigError, error_location, invalid};
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use toml::Table;
+
+/// 配置文件格式，读取后都转换为 TOML 的表，校验和错误信息与 TOML 文件一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl Format {
+    /// 按扩展名判断格式：`.toml`、`.yaml`/`.yml`、`.json`
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Format> {
+        match path.as_ref().extension()?.to_str()? {
+            "toml" => Some(Format::Toml),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+
+    /// 无法从扩展名判断时按 TOML 读取
+    pub fn for_path(path: impl AsRef<Path>) -> Format {
+        Format::from_path(path).unwrap_or(Format::Toml)
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Toml => "toml",
+            Format::Yaml => "yaml",
+            Format::Json => "json",
+        }
+    }
+
+    /// 解析文件内容，升级旧版本后按 `T` 检查结构，返回未解析密钥引用的原始表
+    pub fn parse<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        content: &str,
+    ) -> Result<Table, ConfigError> {
+        let mut table = self.parse_table(path, content)?;
+        let migrated = !migrate(path, &mut table)?.is_empty();
+        if *self == Format::Toml && !migrated {
+            // 未经迁移的 TOML 用原始文本检查结构，错误位置最准确
+            toml::from_str::<T>(content).map_err(|source| invalid(path, content, source))?;
+            return Ok(table);
+        }
+        self.check::<T>(path, content, &table)?;
+        Ok(table)
+    }
+
+    /// 只检查语法，不检查结构
+    pub fn parse_table(&self, path: &str, content: &str) -> Result<Table, ConfigError> {
+        let syntax = |message: String, line: usize, column: usize| ConfigError::Invalid {
+            path: path.to_string(),
+            key: None,
+            line,
+            column,
+            message,
+        };
+        match self {
+            Format::Toml => content
+                .parse()
+                .map_err(|source| invalid(path, content, source)),
+            Format::Yaml => serde_yaml::from_str(content).map_err(|e| {
+                let (line, column) = e
+                    .location()
+                    .map(|location| (location.line(), location.column()))
+                    .unwrap_or((1, 1));
+                syntax(without_location(e.to_string()), line, column)
+            }),
+            Format::Json => serde_json::from_str(content).map_err(|e| {
+                syntax(
+                    without_location(e.to_string()),
+                    e.line().max(1),
+                    e.column().max(1),
+                )
+            }),
+        }
+    }
+
+    /// 结构错误由 TOML 反序列化给出，保证各格式的信息相同，再到原文件中找到配置项的位置
+    pub(super) fn check<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        content: &str,
+        table: &Table,
+    ) -> Result<(), ConfigError> {
+        let text = toml::to_string(table).unwrap_or_default();
+        let error = match toml::from_str::<T>(&text) {
+            Ok(_) => return Ok(()),
+            Err(error) => error,
+        };
+        let (key, _, _) = error_location(&text, &error);
+        let (line, column) = key
+            .as_deref()
+            .and_then(|key| self.locate(content, key))
+            .unwrap_or((1, 1));
+        Err(ConfigError::Invalid {
+            path: path.to_string(),
+            key,
+            line,
+            column,
+            message: error.message().to_string(),
+        })
+    }
+
+    /// 配置项所在的行和列，找不到时依次退到上一级的表
+    pub(super) fn locate(&self, content: &str, key: &str) -> Option<(usize, usize)> {
+        let mut key = key;
+        loop {
+            if let Some(position) = self.find_key(content, key) {
+                return Some(position);
+            }
+            key = key.rsplit_once('.')?.0;
+        }
+    }
+
+    /// 配置项所在的行和列，表和表数组对应表头所在的行
+    pub(super) fn find_key(&self, content: &str, key: &str) -> Option<(usize, usize)> {
+        if key.contains('[') {
+            return self.find_element(content, key);
+        }
+        if *self == Format::Toml {
+            return content.lines().enumerate().find_map(|(index, text)| {
+                let line = index + 1;
+                let found = match text.trim().strip_prefix('[') {
+                    Some(header) => {
+                        header
+                            .trim_start_matches('[')
+                            .split(']')
+                            .next()
+                            .map(str::trim)
+                            == Some(key)
+                    }
+                    None => super::key_at(content, line).as_deref() == Some(key),
+                };
+                found.then(|| (line, text.len() - text.trim_start().len() + 1))
+            });
+        }
+        // YAML 和 JSON 按嵌套顺序依次查找每一级的键
+        let mut segments = key.split('.').peekable();
+        for (index, text) in content.lines().enumerate() {
+            let segment = segments.peek()?;
+            let column = match self {
+                Format::Json => text.find(&format!("\"{}\"", segment)),
+                _ => {
+                    let item = text.trim_start().trim_start_matches("- ").trim_start();
+                    let name = item.trim_start_matches(['"', '\'']);
+                    (name
+                        .strip_prefix(*segment)
+                        .is_some_and(|rest| rest.trim_start_matches(['"', '\'']).starts_with(':')))
+                    .then(|| text.len() - item.len())
+                }
+            };
+            if let Some(column) = column {
+                segments.next();
+                if segments.peek().is_none() {
+                    return Some((index + 1, column + 1));
+                }
+            }
+        }
+        None
+    }
+
+    /// 表数组元素中的配置项，例如 `flink.clusters[1].name`；JSON 只定位到数组本身
+    fn find_element(&self, content: &str, key: &str) -> Option<(usize, usize)> {
+        let (array, rest) = key.split_once('[')?;
+        let (index, field) = rest.split_once("].")?;
+        let index: usize = index.parse().ok()?;
+        let lines: Vec<&str> = content.lines().collect();
+        let indent = |text: &str| text.len() - text.trim_start().len();
+        // 每个元素所在的行范围
+        let mut blocks: Vec<(usize, usize)> = Vec::new();
+        match self {
+            Format::Json => return self.find_key(content, array),
+            Format::Toml => {
+                let header = format!("[[{}]]", array);
+                for (number, text) in lines.iter().enumerate() {
+                    if text.trim_start().starts_with('[') {
+                        if let Some(last) = blocks.last_mut()
+                            && last.1 == lines.len()
+                        {
+                            last.1 = number;
+                        }
+                        if text.trim_start().starts_with(&header) {
+                            blocks.push((number, lines.len()));
+                        }
+                    }
+                }
+            }
+            Format::Yaml => {
+                let (line, _) = self.find_key(content, array)?;
+                let mut item_indent = None;
+                for (number, text) in lines.iter().enumerate().skip(line) {
+                    let trimmed = text.trim_start();
+                    if trimmed.is_empty() || trimmed.starts_with('#') {
+                        continue;
+                    }
+                    let item = trimmed == "-" || trimmed.starts_with("- ");
+                    let level = *item_indent.get_or_insert(indent(text));
+                    if indent(text) < level || (indent(text) == level && !item) {
+                        break;
+                    }
+                    if indent(text) == level {
+                        blocks.push((number, number + 1));
+                    } else if let Some(last) = blocks.last_mut() {
+                        last.1 = number + 1;
+                    }
+                }
+            }
+        }
+        let (start, end) = *blocks.get(index)?;
+        (start..end).find_map(|number| {
+            let text = lines[number];
+            let item = text.trim_start().trim_start_matches('-').trim_start();
+            let name = match self {
+                Format::Toml => item.split_once('=')?.0,
+                _ => item.split_once(':')?.0,
+            };
+            (name.trim().trim_matches(['"', '\'']) == field)
+                .then(|| (number + 1, text.len() - item.len() + 1))
+        })
+    }
+
+    /// 转换为该格式的文本，注释不会保留
+    pub fn render(&self, table: &Table) -> String {
+        match self {
+            Format::Toml => toml::to_string_pretty(table).expect("tables serialize to TOML"),
+            Format::Yaml => serde_yaml::to_string(table).expect("tables serialize to YAML"),
+            Format::Json => {
+                serde_json::to_string_pretty(table).expect("tables serialize to JSON") + "\n"
+            }
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "toml" => Ok(Format::Toml),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("unknown format {}, expected toml, yaml or json", s)),
+        }
+    }
+}
+
+/// serde_yaml 和 serde_json 的错误信息末尾带有位置，位置已单独给出
+fn without_location(message: String) -> String {
+    match message.find(" at line ") {
+        Some(index) => message[..index].to_string(),
+        None => message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_formats_report_the_same_error() {
+        let toml = "version = 2\n[app]\nport = 3000\npage_size = \"ten\"\n\n[search]\nnodes = [\"http://localhost:9200\"]\n";
+        let yaml = "version: 2\napp:\n  port: 3000\n  page_size: ten\nsearch:\n  nodes: [http://localhost:9200]\n";
+        let json = "{\n  \"version\": 2,\n  \"app\": {\n    \"port\": 3000,\n    \"page_size\": \"ten\"\n  },\n  \"search\": {\"nodes\": [\"http://localhost:9200\"]}\n}\n";
+        let mut errors = Vec::new();
+        for (format, content) in [
+            (Format::Toml, toml),
+            (Format::Yaml, yaml),
+            (Format::Json, json),
+        ] {
+            match format.parse::<Config>("config", content) {
+                Err(ConfigError::Invalid {
+                    key, line, message, ..
+                }) => errors.push((key, line, message)),
+                other => panic!("{}: unexpected {:?}", format, other),
+            }
+        }
+        assert_eq!(errors[0].0.as_deref(), Some("app.page_size"));
+        assert_eq!(
+            errors.iter().map(|e| e.1).collect::<Vec<_>>(),
+            vec![4, 4, 5]
+        );
+        assert!(
+            errors
+                .iter()
+                .all(|e| e.0 == errors[0].0 && e.2 == errors[0].2)
+        );
+    }
+
+    #[test]
+    fn test_render_round_trip() {
+        let mut table = Table::try_from(Config::default()).unwrap();
+        table.insert(
+            String::from("version"),
+            toml::Value::Integer(crate::config::CURRENT_VERSION),
+        );
+        for format in [Format::Toml, Format::Yaml, Format::Json] {
+            let text = format.render(&table);
+            assert_eq!(format.parse::<Config>("config", &text).unwrap(), table);
+        }
+        assert_eq!(Format::from_path("config.yml"), Some(Format::Yaml));
+        assert_eq!(Format::for_path("config"), Format::Toml);
+    }
+}
@@ -0,0 +1,595 @@
+use crate::config::migrate::{migrate, migrate_key};
+use crate::config::schema::redact;
+use crate::config::secret::KEY_FILE_ENV;
+use crate::config::show::array_key;
+use crate::config::{Config, ConfigError, Format, SecretResolver, error_location};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// 环境变量前缀，`APP_SEARCH__NODES` 对应 `search.nodes`
+pub const ENV_PREFIX: &str = "APP_";
+/// 选择 profile 的环境变量，不作为配置项
+pub const PROFILE_ENV: &str = "APP_PROFILE";
+const SYSTEM_DIR: &str = "/etc/rust_project";
+
+/// 配置层，后面的层覆盖前面的层
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Layer {
+    Default,
+    System,
+    User,
+    Local,
+    Profile,
+    Env,
+    Cli,
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Layer::Default => "default",
+            Layer::System => "system",
+            Layer::User => "user",
+            Layer::Local => "local",
+            Layer::Profile => "profile",
+            Layer::Env => "env",
+            Layer::Cli => "cli",
+        })
+    }
+}
+
+/// 配置项的来源：所在的层，以及文件路径、环境变量名或命令行参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct Source {
+    pub layer: Layer,
+    pub origin: String,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.layer {
+            Layer::Default => f.write_str("built-in default"),
+            Layer::Env => write!(f, "environment variable {}", self.origin),
+            Layer::Cli => write!(f, "--set {}", self.origin),
+            layer => write!(f, "{} file {}", layer, self.origin),
+        }
+    }
+}
+
+/// 分层读取配置：内置默认值、系统文件、用户文件、当前目录的文件、profile 文件、
+/// `APP_` 环境变量和命令行 `--set key=value`，不存在的文件会被跳过，其它读取错误返回 [`ConfigError::Io`]
+#[derive(Debug, Clone)]
+pub struct ConfigLoader {
+    files: Vec<(Layer, PathBuf)>,
+    format: Option<Format>,
+    profile: Option<String>,
+    env: Vec<(String, String)>,
+    overrides: Vec<String>,
+    secrets: SecretResolver,
+}
+
+impl ConfigLoader {
+    /// `path` 为当前目录的配置文件，profile 文件与它放在同一目录，例如 `config.prod.toml`；
+    /// 系统和用户目录中可以是 config.toml、config.yaml、config.yml 或 config.json
+    pub fn new(path: &str) -> ConfigLoader {
+        let mut files = vec![(Layer::System, find_file(Path::new(SYSTEM_DIR)))];
+        if let Some(dir) = user_config_dir() {
+            files.push((Layer::User, find_file(&dir.join("rust_project"))));
+        }
+        files.push((Layer::Local, PathBuf::from(path)));
+        let env: Vec<(String, String)> = env::vars().collect();
+        let profile = env
+            .iter()
+            .find(|(name, _)| name == PROFILE_ENV)
+            .map(|(_, value)| value.clone());
+        ConfigLoader {
+            files,
+            format: None,
+            profile,
+            env,
+            overrides: Vec::new(),
+            secrets: SecretResolver::from_env(),
+        }
+    }
+
+    pub fn with_profile(mut self, profile: Option<String>) -> ConfigLoader {
+        if profile.is_some() {
+            self.profile = profile;
+        }
+        self
+    }
+
+    /// 替换系统和用户文件的位置，主要用于测试
+    pub fn with_file(mut self, layer: Layer, path: impl Into<PathBuf>) -> ConfigLoader {
+        self.files.retain(|(l, _)| *l != layer);
+        self.files.push((layer, path.into()));
+        self.files.sort_by_key(|(layer, _)| *layer);
+        self
+    }
+
+    /// 指定 `--config` 文件和 profile 文件的格式，不指定时按扩展名判断
+    pub fn with_format(mut self, format: Option<Format>) -> ConfigLoader {
+        self.format = format;
+        self
+    }
+
+    pub fn with_env(mut self, env: Vec<(String, String)>) -> ConfigLoader {
+        self.env = env;
+        self
+    }
+
+    /// `key=value` 形式的覆盖，值按 TOML 解析，解析失败时作为字符串
+    pub fn with_overrides(mut self, overrides: Vec<String>) -> ConfigLoader {
+        self.overrides = overrides;
+        self
+    }
+
+    /// 解密 `enc:` 值使用的密钥文件
+    pub fn with_key_file(mut self, key_file: impl Into<PathBuf>) -> ConfigLoader {
+        self.secrets = SecretResolver::new(Some(key_file.into()));
+        self
+    }
+
+    /// 当前的 profile，也用作功能开关的环境
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// 按顺序列出参与合并的文件，包括不存在的
+    pub fn files(&self) -> Vec<(Layer, PathBuf)> {
+        let mut files = self.files.clone();
+        if let Some(profile) = &self.profile
+            && let Some((_, local)) = self.files.iter().find(|(layer, _)| *layer == Layer::Local)
+        {
+            files.push((Layer::Profile, profile_path(local, profile)));
+        }
+        files
+    }
+
+    pub fn load(&self) -> Result<LoadedConfig, ConfigError> {
+        let mut merged = LoadedConfig::default();
+        let defaults = Table::try_from(Config::default()).expect("defaults are valid TOML");
+        merged.merge(
+            defaults,
+            &Source {
+                layer: Layer::Default,
+                origin: String::new(),
+            },
+        );
+
+        for (layer, path) in self.files() {
+            let origin = path.display().to_string();
+            let content = match fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(source) => {
+                    return Err(ConfigError::Io {
+                        path: origin,
+                        source,
+                    });
+                }
+            };
+            let format = match layer {
+                Layer::Local | Layer::Profile => self.format,
+                _ => None,
+            }
+            .unwrap_or_else(|| Format::for_path(&path));
+            let mut table = format.parse_table(&origin, &content)?;
+            for description in migrate(&origin, &mut table)? {
+                merged.warnings.push(format!(
+                    "{}: {}; run `config migrate {}` to update the file",
+                    origin, description, origin
+                ));
+            }
+            merged.files.insert(origin.clone(), (format, content));
+            merged.merge(table, &Source { layer, origin });
+        }
+
+        for (name, value) in &self.env {
+            let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            if name == PROFILE_ENV || name == KEY_FILE_ENV || key.is_empty() {
+                continue;
+            }
+            let key = key.to_lowercase().replace("__", ".");
+            merged.set_migrated(
+                &key,
+                parse_value(value),
+                Source {
+                    layer: Layer::Env,
+                    origin: name.clone(),
+                },
+            );
+        }
+
+        for arg in &self.overrides {
+            let Some((key, value)) = arg.split_once('=') else {
+                return Err(ConfigError::Override {
+                    arg: arg.clone(),
+                    message: String::from("expected key=value"),
+                });
+            };
+            let key = key.trim();
+            if key.is_empty() || key.split('.').any(|part| part.is_empty()) {
+                return Err(ConfigError::Override {
+                    arg: arg.clone(),
+                    message: String::from("expected a dotted key such as app.port"),
+                });
+            }
+            merged.set_migrated(
+                key,
+                parse_value(value.trim()),
+                Source {
+                    layer: Layer::Cli,
+                    origin: arg.clone(),
+                },
+            );
+        }
+
+        // 先检查结构，再解析密钥引用；`table` 中保留引用本身，不会泄露密码
+        merged.check::<Config>(&merged.table)?;
+        merged.secrets = self.secrets.clone();
+        let mut resolved = merged.table.clone();
+        self.secrets
+            .resolve_table(&mut resolved)
+            .map_err(|(key, source)| ConfigError::Secret {
+                from: merged.source(&key).cloned(),
+                key,
+                source,
+            })?;
+        merged.config =
+            Config::deserialize(Value::Table(resolved)).map_err(|e| ConfigError::Merged {
+                key: None,
+                from: None,
+                message: e.message().to_string(),
+            })?;
+        Ok(merged)
+    }
+}
+
+/// 合并后的配置，记录了每个配置项的来源
+#[derive(Debug, Clone, Default)]
+pub struct LoadedConfig {
+    pub config: Config,
+    pub table: Table,
+    sources: BTreeMap<String, Source>,
+    /// 读取过的文件格式和内容，用于定位错误所在的行
+    files: BTreeMap<String, (Format, String)>,
+    secrets: SecretResolver,
+    warnings: Vec<String>,
+}
+
+impl LoadedConfig {
+    /// 读取时升级了旧版本的文件或改写了旧名称的配置项，由程序在启动时输出一次
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// 配置项（如 `app.port`）最终取值的来源
+    pub fn source(&self, key: &str) -> Option<&Source> {
+        self.sources.get(key)
+    }
+
+    /// 按 `T` 读取合并后的一个部分，例如 [`crate::client::flink::FlinkConfig`] 读取的 `[flink]`；
+    /// 密钥引用已解析，各层都没有该部分时为默认值
+    pub fn section<T: DeserializeOwned + Default>(&self, name: &str) -> Result<T, ConfigError> {
+        let Some(value) = self.table.get(name) else {
+            return Ok(T::default());
+        };
+        let mut table = Table::new();
+        table.insert(name.to_string(), value.clone());
+        self.check::<BTreeMap<String, T>>(&table)?;
+        self.secrets
+            .resolve_table(&mut table)
+            .map_err(|(key, source)| ConfigError::Secret {
+                from: self.source(&key).cloned(),
+                key,
+                source,
+            })?;
+        let mut section = BTreeMap::<String, T>::deserialize(Value::Table(table)).map_err(|e| {
+            ConfigError::Merged {
+                key: Some(name.to_string()),
+                from: None,
+                message: e.message().to_string(),
+            }
+        })?;
+        Ok(section.remove(name).unwrap_or_default())
+    }
+
+    /// 按 `search.nodes` 这样的名称取值，可以是一个表；密码等敏感值显示为 `***`
+    pub fn get(&self, key: &str) -> Option<Value> {
+        let mut value = self.raw(key)?.clone();
+        redact(key, &mut value);
+        Some(value)
+    }
+
+    fn raw(&self, key: &str) -> Option<&Value> {
+        let (first, rest) = match key.split_once('.') {
+            Some((first, rest)) => (first, Some(rest)),
+            None => (key, None),
+        };
+        let mut value = self.table.get(first)?;
+        for part in rest.into_iter().flat_map(|rest| rest.split('.')) {
+            value = value.as_table()?.get(part)?;
+        }
+        Some(value)
+    }
+
+    /// 配置项的来源说明，来自文件时带行号，例如 `local file config.toml:3`
+    pub fn origin(&self, key: &str) -> String {
+        let mut parent = array_key(key);
+        let source = loop {
+            if let Some(source) = self.source(parent) {
+                break source;
+            }
+            match parent.rsplit_once('.') {
+                Some((table, _)) => parent = table,
+                // 没有任何一层给出，例如 `[flink]` 中未写出的配置项
+                None => return String::from("built-in default"),
+            }
+        };
+        let Some((format, content)) = self.files.get(&source.origin) else {
+            return source.to_string();
+        };
+        if matches!(source.layer, Layer::Env | Layer::Cli) {
+            return source.to_string();
+        }
+        match format.find_key(content, key) {
+            Some((line, _)) => format!("{}:{}", source, line),
+            // 表数组元素中没有写出的配置项使用默认值
+            None if key != parent => String::from("built-in default"),
+            // 内联表中的配置项定位到所在的表
+            None => match format.locate(content, key) {
+                Some((line, _)) => format!("{}:{}", source, line),
+                None => source.to_string(),
+            },
+        }
+    }
+
+    /// 所有配置项及其来源，按名称排序
+    pub fn sources(&self) -> &BTreeMap<String, Source> {
+        &self.sources
+    }
+
+    fn merge(&mut self, table: Table, source: &Source) {
+        let mut leaves = Vec::new();
+        flatten("", Value::Table(table), &mut leaves);
+        for (key, value) in leaves {
+            self.set(&key, value, source.clone());
+        }
+    }
+
+    /// 环境变量和 `--set` 的配置项，旧版本的名称按迁移改写，不会被静默忽略
+    fn set_migrated(&mut self, key: &str, value: Value, source: Source) {
+        match migrate_key(key, value.clone()) {
+            Some((table, applied)) => {
+                for description in applied {
+                    self.warnings.push(format!("{}: {}", source, description));
+                }
+                self.merge(table, &source);
+            }
+            None => self.set(key, value, source),
+        }
+    }
+
+    fn set(&mut self, key: &str, value: Value, source: Source) {
+        let parts: Vec<&str> = key.split('.').collect();
+        let (last, parents) = parts.split_last().expect("split yields at least one part");
+        let mut table = &mut self.table;
+        for part in parents {
+            let entry = table
+                .entry(part.to_string())
+                .or_insert_with(|| Value::Table(Table::new()));
+            if !entry.is_table() {
+                *entry = Value::Table(Table::new());
+            }
+            table = entry.as_table_mut().expect("just made a table");
+        }
+        // 整个表被替换时，原来子配置项的来源不再有效
+        let prefix = format!("{}.", key);
+        self.sources.retain(|k, _| !k.starts_with(&prefix));
+        let mut leaves = Vec::new();
+        flatten(key, value.clone(), &mut leaves);
+        for (leaf, _) in leaves {
+            self.sources.insert(leaf, source.clone());
+        }
+        table.insert(last.to_string(), value);
+    }
+
+    fn check<T: DeserializeOwned>(&self, table: &Table) -> Result<(), ConfigError> {
+        // 合并后的值没有位置信息，从重新生成的 TOML 文本中找到出错的配置项
+        let content = toml::to_string(table).unwrap_or_default();
+        let error = match toml::from_str::<T>(&content) {
+            Ok(_) => return Ok(()),
+            Err(error) => error,
+        };
+        let (key, _, _) = error_location(&content, &error);
+        let message = error.message().to_string();
+        let from = key.as_ref().and_then(|key| self.source(key)).cloned();
+        if let (Some(key), Some(source)) = (&key, &from)
+            && let Some((format, content)) = self.files.get(&source.origin)
+            && let Some((line, column)) = format.locate(content, key)
+        {
+            return Err(ConfigError::Invalid {
+                path: source.origin.clone(),
+                key: Some(key.clone()),
+                line,
+                column,
+                message,
+            });
+        }
+        Err(ConfigError::Merged { key, from, message })
+    }
+}
+
+pub(super) fn flatten(prefix: &str, value: Value, leaves: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Table(table) if !table.is_empty() || prefix.is_empty() => {
+            for (key, value) in table {
+                let key = if prefix.is_empty() {
+                    key
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&key, value, leaves);
+            }
+        }
+        value => leaves.push((prefix.to_string(), value)),
+    }
+}
+
+fn parse_value(value: &str) -> Value {
+    format!("value = {}", value)
+        .parse::<Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(value.to_string()))
+}
+
+fn profile_path(local: &Path, profile: &str) -> PathBuf {
+    let stem = local
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("config");
+    let name = match local.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => format!("{}.{}.{}", stem, profile, ext),
+        None => format!("{}.{}", stem, profile),
+    };
+    local.with_file_name(name)
+}
+
+/// 目录中第一个存在的配置文件，都不存在时为 config.toml
+fn find_file(dir: &Path) -> PathBuf {
+    ["toml", "yaml", "yml", "json"]
+        .iter()
+        .map(|extension| dir.join(format!("config.{}", extension)))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| dir.join("config.toml"))
+}
+
+fn user_config_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loader(dir: &Path) -> ConfigLoader {
+        ConfigLoader::new(dir.join("config.toml").to_str().unwrap())
+            .with_file(Layer::System, dir.join("system.toml"))
+            .with_file(Layer::User, dir.join("user.toml"))
+            .with_env(Vec::new())
+    }
+
+    #[test]
+    fn test_layers() {
+        let dir = env::temp_dir().join(format!("rust_project_layers_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("system.toml"),
+            "[app]\nport = 8000\npage_size = 50\n",
+        )
+        .unwrap();
+        fs::write(dir.join("config.toml"), "[app]\nport = 8080\n").unwrap();
+        // 版本 1 的文件，`search.address` 迁移为 `search.nodes`
+        fs::write(
+            dir.join("config.prod.toml"),
+            "[search]\naddress = \"http://es-prod:9200\"\n",
+        )
+        .unwrap();
+
+        let loaded = loader(&dir)
+            .with_profile(Some(String::from("prod")))
+            .with_env(vec![
+                (String::from("APP_APP__PAGE_SIZE"), String::from("20")),
+                (String::from("APP_PROFILE"), String::from("test")),
+                (String::from("HOME"), String::from("/root")),
+            ])
+            .with_overrides(vec![String::from("app.port=4000")])
+            .load()
+            .unwrap();
+        assert_eq!(loaded.config.app.port, 4000);
+        assert_eq!(loaded.config.app.page_size, 20);
+        assert_eq!(loaded.config.search.nodes, vec!["http://es-prod:9200"]);
+        assert_eq!(loaded.source("app.port").unwrap().layer, Layer::Cli);
+        assert_eq!(
+            loaded.source("app.page_size").unwrap().to_string(),
+            "environment variable APP_APP__PAGE_SIZE"
+        );
+        assert_eq!(loaded.source("search.nodes").unwrap().layer, Layer::Profile);
+
+        let loaded = loader(&dir).load().unwrap();
+        assert_eq!(loaded.config.app.port, 8080);
+        assert_eq!(loaded.source("app.page_size").unwrap().layer, Layer::System);
+        assert_eq!(loaded.source("search.nodes").unwrap().layer, Layer::Default);
+
+        // 环境变量使用了旧版本的名称
+        let loaded = loader(&dir)
+            .with_env(vec![(
+                String::from("APP_SEARCH__ADDRESS"),
+                String::from("http://es-env:9200"),
+            )])
+            .load()
+            .unwrap();
+        assert_eq!(loaded.config.search.nodes, vec!["http://es-env:9200"]);
+        assert_eq!(loaded.source("search.nodes").unwrap().layer, Layer::Env);
+        assert!(loaded.get("search.address").is_none());
+        assert_eq!(
+            loaded.warnings(),
+            [
+                "environment variable APP_SEARCH__ADDRESS: search.address was moved into the search.nodes list"
+            ]
+        );
+
+        fs::write(dir.join("user.toml"), "[app]\npage_size = \"ten\"\n").unwrap();
+        let error = loader(&dir).load().unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+        match error {
+            ConfigError::Invalid {
+                path, key, line, ..
+            } => {
+                assert!(path.ends_with("user.toml"));
+                assert_eq!(key.as_deref(), Some("app.page_size"));
+                assert_eq!(line, 2);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_overrides() {
+        let dir = env::temp_dir().join("rust_project_layers_missing");
+        let error = loader(&dir)
+            .with_overrides(vec![String::from("app.port")])
+            .load()
+            .unwrap_err();
+        assert!(matches!(error, ConfigError::Override { .. }));
+
+        let error = loader(&dir)
+            .with_overrides(vec![String::from("app.port=high")])
+            .load()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "app.port: (from --set app.port=high) invalid type: string \"high\", expected i32"
+        );
+
+        // 存在但无法读取的文件不能被当作不存在
+        let error = loader(&dir)
+            .with_file(Layer::Local, env::temp_dir())
+            .load()
+            .unwrap_err();
+        assert!(matches!(error, ConfigError::Io { .. }), "{}", error);
+    }
+}
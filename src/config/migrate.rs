@@ -0,0 +1,121 @@
+use crate::config::ConfigError;
+use toml::{Table, Value};
+
+/// 当前的配置文件版本，没有 `version` 的文件视为版本 1
+pub const CURRENT_VERSION: i64 = 2;
+
+/// 把版本 `from` 的表升级到 `from + 1`，`apply` 返回表是否有变化
+struct Migration {
+    from: i64,
+    description: &'static str,
+    /// 被改名或移除的配置项，环境变量和 `--set` 使用这些名称时同样需要迁移
+    renamed: &'static [&'static str],
+    apply: fn(&mut Table) -> bool,
+}
+
+/// 重命名或调整配置项时在这里追加迁移，同时增加 [`CURRENT_VERSION`]
+const MIGRATIONS: &[Migration] = &[Migration {
+    from: 1,
+    description: "search.address was moved into the search.nodes list",
+    renamed: &["search.address"],
+    apply: search_nodes,
+}];
+
+/// 把旧版本的表升级到当前版本并设置 `version`，返回实际改动了表的迁移说明，
+/// 由调用方提示用户（见 [`crate::config::LoadedConfig::warnings`]）
+pub fn migrate(path: &str, table: &mut Table) -> Result<Vec<&'static str>, ConfigError> {
+    let version = match table.get("version") {
+        None => 1,
+        Some(Value::Integer(version)) if (1..=CURRENT_VERSION).contains(version) => *version,
+        Some(other) => {
+            return Err(ConfigError::Version {
+                path: path.to_string(),
+                version: other.to_string(),
+            });
+        }
+    };
+    let applied: Vec<&'static str> = MIGRATIONS
+        .iter()
+        .filter(|migration| migration.from >= version)
+        .filter_map(|migration| (migration.apply)(table).then_some(migration.description))
+        .collect();
+    if version < CURRENT_VERSION {
+        table.insert(String::from("version"), Value::Integer(CURRENT_VERSION));
+    }
+    Ok(applied)
+}
+
+/// 环境变量或 `--set` 使用了旧版本的名称时，把这一项按迁移改写为当前版本的表，
+/// 同时返回执行过的迁移说明；不是旧名称时返回 `None`
+pub fn migrate_key(key: &str, value: Value) -> Option<(Table, Vec<&'static str>)> {
+    let first = MIGRATIONS
+        .iter()
+        .position(|migration| migration.renamed.contains(&key))?;
+    let mut table = Table::new();
+    let (parents, last) = match key.rsplit_once('.') {
+        Some((parents, last)) => (parents.split('.').collect(), last),
+        None => (Vec::new(), key),
+    };
+    let mut current = &mut table;
+    for part in parents {
+        current = current
+            .entry(part)
+            .or_insert_with(|| Value::Table(Table::new()))
+            .as_table_mut()
+            .expect("just inserted a table");
+    }
+    current.insert(last.to_string(), value);
+    let applied = MIGRATIONS[first..]
+        .iter()
+        .filter_map(|migration| (migration.apply)(&mut table).then_some(migration.description))
+        .collect();
+    Some((table, applied))
+}
+
+/// 版本 1 → 2：`search.address` 改为 `search.nodes = [address]`
+fn search_nodes(table: &mut Table) -> bool {
+    let Some(Value::Table(search)) = table.get_mut("search") else {
+        return false;
+    };
+    let Some(address) = search.remove("address") else {
+        return false;
+    };
+    search
+        .entry("nodes")
+        .or_insert_with(|| Value::Array(vec![address]));
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_migrate() {
+        let old = "[app]\nport = 3000\npage_size = 10\n\n[search]\naddress = \"http://es:9200\"\n";
+        let config = Config::parse("config.toml", old).unwrap();
+        assert_eq!(config.search.nodes, vec!["http://es:9200"]);
+
+        let mut table: Table = old.parse().unwrap();
+        assert_eq!(migrate("config.toml", &mut table).unwrap().len(), 1);
+        assert_eq!(table["version"].as_integer(), Some(CURRENT_VERSION));
+        assert!(migrate("config.toml", &mut table).unwrap().is_empty());
+
+        let (table_for_key, applied) = migrate_key(
+            "search.address",
+            Value::String(String::from("http://es:9200")),
+        )
+        .unwrap();
+        assert_eq!(applied.len(), 1);
+        assert_eq!(
+            table_for_key.to_string(),
+            "[search]\nnodes = [\"http://es:9200\"]\n"
+        );
+        assert!(migrate_key("search.nodes", Value::Array(Vec::new())).is_none());
+
+        table.insert(String::from("version"), Value::Integer(CURRENT_VERSION + 1));
+        let error = migrate("config.toml", &mut table).unwrap_err();
+        assert!(matches!(error, ConfigError::Version { .. }), "{}", error);
+    }
+}
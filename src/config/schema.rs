@@ -0,0 +1,737 @@
+use crate::config::CURRENT_VERSION;
+use crate::config::secret::REDACTED;
+use serde_json::{Value, json};
+use std::fmt::Write;
+
+/// 配置项的类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldType {
+    String,
+    /// http/https 地址
+    Url,
+    /// 可以使用 `${env:...}`、`${file:...}` 或 `enc:` 引用的敏感值
+    Secret,
+    Integer {
+        min: i64,
+        max: i64,
+    },
+    Boolean,
+    StringArray,
+    /// 至少一个 http/https 地址
+    UrlArray,
+    /// 开关：布尔值、0 到 100 的百分比，或按环境覆盖的表
+    Toggle,
+    Enum(&'static [&'static str]),
+}
+
+impl FieldType {
+    fn name(&self) -> String {
+        match self {
+            FieldType::String => String::from("string"),
+            FieldType::Url => String::from("URL"),
+            FieldType::Secret => String::from("secret"),
+            FieldType::Integer { min, max } => format!("integer ({}..={})", min, max),
+            FieldType::Boolean => String::from("boolean"),
+            FieldType::StringArray => String::from("array of strings"),
+            FieldType::UrlArray => String::from("array of URLs"),
+            FieldType::Toggle => String::from("boolean, percentage or table"),
+            FieldType::Enum(values) => values.join(" \\| "),
+        }
+    }
+
+    fn schema(&self) -> Value {
+        match self {
+            FieldType::String => json!({"type": "string"}),
+            FieldType::Url => json!({"type": "string", "format": "uri"}),
+            FieldType::Secret => json!({"type": "string", "writeOnly": true}),
+            FieldType::Integer { min, max } => {
+                json!({"type": "integer", "minimum": min, "maximum": max})
+            }
+            FieldType::Boolean => json!({"type": "boolean"}),
+            FieldType::StringArray => json!({"type": "array", "items": {"type": "string"}}),
+            FieldType::UrlArray => json!({
+                "type": "array",
+                "items": {"type": "string", "format": "uri"},
+                "minItems": 1
+            }),
+            FieldType::Toggle => {
+                let rollout = json!({"oneOf": [
+                    {"type": "boolean"},
+                    {"type": "integer", "minimum": 0, "maximum": 100}
+                ]});
+                json!({"oneOf": [
+                    rollout["oneOf"][0],
+                    rollout["oneOf"][1],
+                    {"type": "object", "additionalProperties": rollout}
+                ]})
+            }
+            FieldType::Enum(values) => json!({"type": "string", "enum": values}),
+        }
+    }
+}
+
+/// 配置项说明，`key` 中的 `[]` 表示表数组（`[[...]]`）中的每一项
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub key: &'static str,
+    pub kind: FieldType,
+    pub required: bool,
+    /// TOML 写法的默认值
+    pub default: Option<&'static str>,
+    /// 必填配置项在模板中写出的值，也是内置默认层使用的值
+    pub example: Option<&'static str>,
+    pub description: &'static str,
+}
+
+const fn field(
+    key: &'static str,
+    kind: FieldType,
+    default: Option<&'static str>,
+    description: &'static str,
+) -> Field {
+    Field {
+        key,
+        kind,
+        required: false,
+        default,
+        example: None,
+        description,
+    }
+}
+
+const fn required(
+    key: &'static str,
+    kind: FieldType,
+    example: Option<&'static str>,
+    description: &'static str,
+) -> Field {
+    Field {
+        key,
+        kind,
+        required: true,
+        default: None,
+        example,
+        description,
+    }
+}
+
+const PORT: FieldType = FieldType::Integer { min: 1, max: 65535 };
+const COUNT: FieldType = FieldType::Integer {
+    min: 0,
+    max: u32::MAX as i64,
+};
+
+/// config.toml 的所有配置项，新增配置时同时在这里登记，测试会检查两者是否一致
+pub const FIELDS: &[Field] = &[
+    field(
+        "version",
+        FieldType::Integer {
+            min: 1,
+            max: CURRENT_VERSION,
+        },
+        Some("2"),
+        "Config format version; older files are upgraded automatically with a warning.",
+    ),
+    required(
+        "app.port",
+        PORT,
+        Some("3000"),
+        "Port the application listens on.",
+    ),
+    required(
+        "app.page_size",
+        FieldType::Integer { min: 1, max: 1000 },
+        Some("10"),
+        "Number of search results per page.",
+    ),
+    required(
+        "search.nodes",
+        FieldType::UrlArray,
+        Some("[\"http://localhost:9200\"]"),
+        "Base URLs of the search service nodes (search.address before version 2).",
+    ),
+    field(
+        "search.username",
+        FieldType::String,
+        None,
+        "User for basic authentication against the search service.",
+    ),
+    field(
+        "search.password",
+        FieldType::Secret,
+        None,
+        "Password for the search service; requires search.username.",
+    ),
+    field(
+        "features.<name>",
+        FieldType::Toggle,
+        None,
+        "Feature toggle: true/false, a rollout percentage, or values per profile such as \
+         { default = 10, prod = 0 }.",
+    ),
+    required(
+        "flink.clusters[].name",
+        FieldType::String,
+        None,
+        "Name used to select the cluster on the command line.",
+    ),
+    required(
+        "flink.clusters[].url",
+        FieldType::Url,
+        None,
+        "JobManager REST address.",
+    ),
+    field(
+        "flink.clusters[].username",
+        FieldType::String,
+        None,
+        "User for basic authentication.",
+    ),
+    field(
+        "flink.clusters[].password",
+        FieldType::Secret,
+        None,
+        "Password for basic authentication.",
+    ),
+    field(
+        "flink.clusters[].timeout_secs",
+        COUNT,
+        Some("10"),
+        "Timeout of a single REST request in seconds.",
+    ),
+    field(
+        "flink.clusters[].retries",
+        COUNT,
+        Some("0"),
+        "Retries of idempotent requests such as GET after network errors and HTTP 5xx; POST and PATCH are never retried.",
+    ),
+    field(
+        "flink.clusters[].history_url",
+        FieldType::Url,
+        None,
+        "History Server address used to look up archived jobs.",
+    ),
+    field(
+        "flink.clusters[].sql_gateway_url",
+        FieldType::Url,
+        None,
+        "SQL Gateway address.",
+    ),
+    field(
+        "flink.naming.patterns",
+        FieldType::StringArray,
+        Some("[]"),
+        "Regular expressions with named groups that parse job names into metadata fields.",
+    ),
+    field(
+        "flink.storm.max_restarts",
+        COUNT,
+        Some("3"),
+        "Restarts within the window that count as a restart storm.",
+    ),
+    field(
+        "flink.storm.window_secs",
+        COUNT,
+        Some("600"),
+        "Length of the restart storm window in seconds.",
+    ),
+    field(
+        "flink.storm.cooldown_secs",
+        COUNT,
+        Some("1800"),
+        "Minimum time between two remediations of the same job in seconds.",
+    ),
+    field(
+        "flink.storm.rules[].filter",
+        FieldType::String,
+        Some("\"\""),
+        "Job filter such as `env=prod team=orders`; empty matches every job.",
+    ),
+    required(
+        "flink.storm.rules[].action",
+        FieldType::Enum(&["none", "stop_with_savepoint", "cancel", "command"]),
+        None,
+        "Remediation for matching jobs.",
+    ),
+    field(
+        "flink.storm.rules[].target_directory",
+        FieldType::String,
+        None,
+        "Savepoint directory for `stop_with_savepoint`.",
+    ),
+    field(
+        "flink.storm.rules[].command",
+        FieldType::String,
+        None,
+        "Shell command for `command`, job details are passed as FLINK_* variables.",
+    ),
+    field(
+        "flink.lint.min_checkpoint_interval_secs",
+        COUNT,
+        Some("10"),
+        "Checkpoint intervals below this are reported by `flink_cli lint`.",
+    ),
+    field(
+        "flink.lint.state_backends",
+        FieldType::StringArray,
+        Some("[\"hashmap\", \"rocksdb\"]"),
+        "State backends jobs may use.",
+    ),
+    field(
+        "flink.lint.require_max_parallelism",
+        FieldType::Boolean,
+        Some("true"),
+        "Warn when pipeline.max-parallelism is not set.",
+    ),
+];
+
+/// 配置项是否为密码等敏感值，表数组元素写作 `flink.clusters[].password` 或 `flink.clusters[0].password`
+pub fn is_secret(key: &str) -> bool {
+    let mut normalized = String::with_capacity(key.len());
+    let mut in_index = false;
+    for c in key.chars() {
+        match c {
+            '[' => {
+                in_index = true;
+                normalized.push(c);
+            }
+            ']' => {
+                in_index = false;
+                normalized.push(c);
+            }
+            _ if in_index => {}
+            _ => normalized.push(c),
+        }
+    }
+    FIELDS
+        .iter()
+        .any(|field| field.key == normalized && field.kind == FieldType::Secret)
+}
+
+/// 把 `key` 对应的值中所有敏感配置项替换为 `***`，`key` 为空表示整个配置
+pub fn redact(key: &str, value: &mut toml::Value) {
+    match value {
+        toml::Value::Table(table) => {
+            for (name, value) in table.iter_mut() {
+                let child = if key.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}.{}", key, name)
+                };
+                redact(&child, value);
+            }
+        }
+        toml::Value::Array(items) if items.iter().all(toml::Value::is_table) => {
+            for item in items {
+                redact(&format!("{}[]", key), item);
+            }
+        }
+        value => {
+            if is_secret(key) {
+                *value = toml::Value::String(String::from(REDACTED));
+            }
+        }
+    }
+}
+
+/// 所有配置项的 JSON Schema，可以在 config.toml 开头用 `#:schema` 指定给编辑器
+pub fn json_schema() -> Value {
+    let mut root = object_schema();
+    for field in FIELDS {
+        let mut schema = field.kind.schema();
+        schema["description"] = json!(field.description);
+        if let Some(default) = field.default {
+            schema["default"] = toml_to_json(default);
+        }
+        if let Some(example) = field.example {
+            schema["examples"] = json!([toml_to_json(example)]);
+        }
+        insert(&mut root, field.key, field.required, schema);
+    }
+    root["$schema"] = json!("https://json-schema.org/draft/2020-12/schema");
+    root["title"] = json!("config.toml");
+    root
+}
+
+fn object_schema() -> Value {
+    json!({"type": "object", "properties": {}})
+}
+
+/// 按 `a.b[].c` 的路径创建嵌套的 object/array schema
+fn insert(schema: &mut Value, key: &str, required: bool, leaf: Value) {
+    let Some((first, rest)) = key.split_once('.') else {
+        // `<name>` 表示任意名称的配置项
+        if key.starts_with('<') {
+            schema["additionalProperties"] = leaf;
+            return;
+        }
+        schema["properties"][key] = leaf;
+        if required {
+            mark_required(schema, key);
+        }
+        return;
+    };
+    match first.strip_suffix("[]") {
+        Some(name) => {
+            let properties = &mut schema["properties"];
+            if properties.get(name).is_none() {
+                properties[name] = json!({"type": "array", "items": object_schema()});
+            }
+            insert(&mut properties[name]["items"], rest, required, leaf);
+        }
+        None => {
+            let properties = &mut schema["properties"];
+            if properties.get(first).is_none() {
+                properties[first] = object_schema();
+            }
+            insert(&mut properties[first], rest, required, leaf);
+            // 必填配置项所在的表也必须存在，表数组中的除外（数组本身可以为空）
+            if required && !rest.contains("[]") {
+                mark_required(schema, first);
+            }
+        }
+    }
+}
+
+fn mark_required(schema: &mut Value, key: &str) {
+    let list = schema
+        .as_object_mut()
+        .expect("object schema")
+        .entry("required")
+        .or_insert_with(|| json!([]));
+    let list = list.as_array_mut().expect("required is an array");
+    if !list.contains(&json!(key)) {
+        list.push(json!(key));
+    }
+}
+
+fn toml_to_json(literal: &str) -> Value {
+    let value: toml::Value = format!("value = {}", literal)
+        .parse::<toml::Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .expect("defaults are TOML literals");
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+/// 所有配置项的 Markdown 参考文档，每个表一节
+pub fn markdown() -> String {
+    let mut out = String::from(
+        "# config.toml reference\n\n\
+         Generated by `flink_cli config schema --format markdown`, do not edit by hand.\n\n\
+         Secret values accept `${env:NAME}`, `${file:/path}` or an `enc:` value from \
+         `flink_cli config encrypt`.\n",
+    );
+    let mut section = None;
+    for field in FIELDS {
+        let (table, name) = field.key.rsplit_once('.').unwrap_or(("", field.key));
+        if section != Some(table) {
+            section = Some(table);
+            let header = match header(table) {
+                Some(header) => format!("`{}`", header),
+                None => String::from("Top level"),
+            };
+            let _ = write!(
+                out,
+                "\n## {}\n\n| Key | Type | Default | Description |\n| --- | --- | --- | --- |\n",
+                header
+            );
+        }
+        let default = match (field.default, field.required, field.example) {
+            (Some(default), _, _) => format!("`{}`", default),
+            (None, true, Some(example)) => format!("required, e.g. `{}`", example),
+            (None, true, None) => String::from("required"),
+            (None, false, _) => String::new(),
+        };
+        let _ = writeln!(
+            out,
+            "| `{}` | {} | {} | {} |",
+            name,
+            field.kind.name(),
+            default,
+            field.description.replace('|', "\\|")
+        );
+    }
+    out
+}
+
+/// 带注释的默认配置文件，`flink_cli config init` 写出的内容：有默认值或示例值的配置项直接写出，
+/// 其它配置项和表数组注释掉
+pub fn template() -> String {
+    let mut out = String::from(
+        "# rust_project configuration, generated by `flink_cli config init`.\n\
+         # Every key is listed with its default; uncomment the optional ones to use them.\n\
+         # Secret values accept ${env:NAME}, ${file:/path} or an enc: value from\n\
+         # `flink_cli config encrypt`.\n\n",
+    );
+    let mut section = None;
+    for field in FIELDS {
+        let (table, name) = field.key.rsplit_once('.').unwrap_or(("", field.key));
+        let array = table.ends_with("[]");
+        if section != Some(table) {
+            section = Some(table);
+            if let Some(header) = header(table) {
+                let _ = writeln!(out, "{}{}\n", if array { "# " } else { "" }, header);
+            }
+        }
+        let _ = writeln!(out, "# {}", field.description);
+        match field.default.or(field.example) {
+            Some(default) if !array => {
+                let _ = writeln!(out, "{} = {}\n", name, default);
+            }
+            default => {
+                let value = default
+                    .map(String::from)
+                    .unwrap_or_else(|| placeholder(field.kind));
+                let _ = writeln!(out, "# {} = {}\n", name, value);
+            }
+        }
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+/// 表头，顶层配置项没有表头
+fn header(table: &str) -> Option<String> {
+    if table.is_empty() {
+        return None;
+    }
+    Some(match table.strip_suffix("[]") {
+        Some(array) => format!("[[{}]]", array),
+        None => format!("[{}]", table),
+    })
+}
+
+/// 没有默认值的配置项在模板中的示例值
+fn placeholder(kind: FieldType) -> String {
+    match kind {
+        FieldType::String => String::from("\"\""),
+        FieldType::Url => String::from("\"http://localhost:8081\""),
+        FieldType::Secret => String::from("\"${env:PASSWORD}\""),
+        FieldType::Integer { min, .. } => min.to_string(),
+        FieldType::Boolean => String::from("false"),
+        FieldType::StringArray => String::from("[]"),
+        FieldType::UrlArray => String::from("[\"http://localhost:9200\"]"),
+        FieldType::Toggle => String::from("{ default = 10, staging = 100, prod = 0 }"),
+        FieldType::Enum(values) => format!("{:?}", values.first().copied().unwrap_or_default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::flink::FlinkConfig;
+    use crate::config::Config;
+    use serde::Deserialize;
+
+    /// 序列化后出现的配置项，数组下标替换为 `[]`
+    fn keys(prefix: &str, value: &toml::Value, found: &mut Vec<(String, toml::Value)>) {
+        match value {
+            toml::Value::Table(table) => {
+                for (name, value) in table {
+                    let key = if prefix.is_empty() {
+                        name.clone()
+                    } else {
+                        format!("{}.{}", prefix, name)
+                    };
+                    keys(&key, value, found);
+                }
+            }
+            toml::Value::Array(items)
+                if !items.is_empty() && items.iter().all(|i| i.is_table()) =>
+            {
+                for item in items {
+                    keys(&format!("{}[]", prefix), item, found);
+                }
+            }
+            value => found.push((prefix.to_string(), value.clone())),
+        }
+    }
+
+    #[test]
+    fn test_defaults_match_code() {
+        let flink: FlinkConfig = toml::from_str(
+            r#"
+            [[clusters]]
+            name = "prod"
+            url = "http://flink:8081"
+
+            [[storm.rules]]
+            action = "cancel"
+            "#,
+        )
+        .unwrap();
+        let mut value = toml::Value::try_from(Config::default()).unwrap();
+        value
+            .as_table_mut()
+            .unwrap()
+            .insert(String::from("flink"), toml::Value::try_from(flink).unwrap());
+        let mut found = Vec::new();
+        keys("", &value, &mut found);
+        for (key, value) in found {
+            let field = FIELDS
+                .iter()
+                .find(|field| field.key == key)
+                .unwrap_or_else(|| panic!("{} is missing from FIELDS", key));
+            if let Some(default) = field.default.or(field.example) {
+                assert_eq!(
+                    toml_to_json(default),
+                    serde_json::to_value(&value).unwrap(),
+                    "{}",
+                    key
+                );
+            }
+        }
+    }
+
+    /// 写出 FIELDS 中所有配置项（`skip` 除外）的文件：有默认值的用默认值，其它用示例值；
+    /// 表数组按其中枚举配置项的每个取值各写一个元素，覆盖只在某个取值下才有的配置项
+    fn sample(skip: Option<&str>) -> toml::Table {
+        fn put(table: &mut toml::Table, prefix: &str, rest: &str, field: &Field, index: usize) {
+            let Some((first, rest)) = rest.split_once('.') else {
+                let value = match field.kind {
+                    FieldType::Enum(values) => toml::Value::from(values[index % values.len()]),
+                    kind => {
+                        let literal = field.default.or(field.example).map(String::from);
+                        let literal = literal.unwrap_or_else(|| placeholder(kind));
+                        toml::Value::try_from(toml_to_json(&literal)).unwrap()
+                    }
+                };
+                table.insert(rest.replace("<name>", "name"), value);
+                return;
+            };
+            let key = format!("{}{}", prefix, first);
+            let child = match first.strip_suffix("[]") {
+                Some(array) => {
+                    let elements = FIELDS
+                        .iter()
+                        .filter(|f| f.key.starts_with(&format!("{}.", key)))
+                        .filter_map(|f| match f.kind {
+                            FieldType::Enum(values) => Some(values.len()),
+                            _ => None,
+                        })
+                        .max()
+                        .unwrap_or(1);
+                    let items = table.entry(array).or_insert_with(|| {
+                        toml::Value::Array(vec![toml::Value::Table(toml::Table::new()); elements])
+                    });
+                    for (index, item) in items.as_array_mut().unwrap().iter_mut().enumerate() {
+                        let item = item.as_table_mut().unwrap();
+                        put(item, &format!("{}.", key), rest, field, index);
+                    }
+                    return;
+                }
+                None => table
+                    .entry(first)
+                    .or_insert_with(|| toml::Value::Table(toml::Table::new())),
+            };
+            put(
+                child.as_table_mut().unwrap(),
+                &format!("{}.", key),
+                rest,
+                field,
+                index,
+            );
+        }
+        let mut root = toml::Table::new();
+        for field in FIELDS.iter().filter(|field| Some(field.key) != skip) {
+            put(&mut root, "", field.key, field, 0);
+        }
+        root
+    }
+
+    /// 按 `sample` 读取后再序列化为 JSON 得到的配置项，值为 `None` 的 Option 字段也会出现
+    fn read_back(sample: toml::Table) -> Result<Vec<String>, toml::de::Error> {
+        fn json_keys(prefix: &str, value: &serde_json::Value, found: &mut Vec<String>) {
+            match value {
+                serde_json::Value::Object(map) if !prefix.starts_with("features.") => {
+                    for (name, value) in map {
+                        let key = match prefix {
+                            "" => name.clone(),
+                            "features" => String::from("features.<name>"),
+                            prefix => format!("{}.{}", prefix, name),
+                        };
+                        json_keys(&key, value, found);
+                    }
+                }
+                serde_json::Value::Array(items)
+                    if !items.is_empty() && items.iter().all(|i| i.is_object()) =>
+                {
+                    for item in items {
+                        json_keys(&format!("{}[]", prefix), item, found);
+                    }
+                }
+                _ => found.push(prefix.to_string()),
+            }
+        }
+        let config = Config::deserialize(toml::Value::Table(sample.clone()))?;
+        let flink = sample
+            .get("flink")
+            .cloned()
+            .unwrap_or(toml::Value::Table(toml::Table::new()));
+        let flink = FlinkConfig::deserialize(flink)?;
+        let mut value = serde_json::to_value(config).unwrap();
+        value["flink"] = serde_json::to_value(flink).unwrap();
+        let mut found = Vec::new();
+        json_keys("", &value, &mut found);
+        found.sort();
+        found.dedup();
+        Ok(found)
+    }
+
+    #[test]
+    fn test_fields_match_both_ways() {
+        // `version` 由迁移处理，不在配置结构中
+        let mut expected: Vec<String> = FIELDS
+            .iter()
+            .filter(|field| field.key != "version")
+            .map(|field| field.key.to_string())
+            .collect();
+        expected.sort();
+        // 代码中多出的配置项（包括 Option 字段）和 FIELDS 中过时的配置项都会出现在差异里
+        assert_eq!(read_back(sample(None)).unwrap(), expected);
+
+        // 必填的配置项缺少时读取失败，有默认值的配置项可以省略
+        for field in FIELDS.iter().filter(|field| field.key != "version") {
+            let result = read_back(sample(Some(field.key)));
+            if field.required {
+                assert!(
+                    result.is_err(),
+                    "{} is required in FIELDS but optional in code",
+                    field.key
+                );
+            }
+            if field.default.is_some() {
+                assert!(
+                    result.is_ok(),
+                    "{} has a default in FIELDS but is required in code",
+                    field.key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_template_is_the_default() {
+        let content = template();
+        assert_eq!(
+            Config::parse("config.toml", &content).unwrap(),
+            Config::default()
+        );
+        let flink: toml::Table = content.parse().unwrap();
+        assert_eq!(flink["version"].as_integer(), Some(CURRENT_VERSION));
+        let flink = FlinkConfig::deserialize(flink["flink"].clone()).unwrap();
+        assert_eq!(
+            toml::Value::try_from(flink).unwrap(),
+            toml::Value::try_from(FlinkConfig::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_generated_files_are_current() {
+        let schema = serde_json::to_string_pretty(&json_schema()).unwrap() + "\n";
+        assert_eq!(include_str!("../../config.schema.json"), schema);
+        assert_eq!(include_str!("../../docs/config.md"), markdown());
+    }
+}
@@ -0,0 +1,268 @@
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use regex::{Captures, Regex};
+use ring::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use thiserror::Error;
+use toml::{Table, Value};
+
+/// 指定解密密钥文件的环境变量，不作为配置项
+pub const KEY_FILE_ENV: &str = "APP_KEY_FILE";
+/// 加密值的前缀，后面是 base64 编码的 nonce 和密文
+pub const ENCRYPTED_PREFIX: &str = "enc:";
+/// 输出中代替密码的文本
+pub const REDACTED: &str = "***";
+
+/// `${env:NAME}` 或 `${file:/path}` 引用
+static REFERENCE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\$\{(env|file):([^}]+)\}").expect("valid regex"));
+
+#[derive(Debug, Error)]
+pub enum SecretError {
+    #[error("environment variable {0} is not set")]
+    MissingEnv(String),
+    #[error("failed to read secret file {path}: {source}")]
+    File {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("no key file to decrypt enc: values, set {KEY_FILE_ENV}")]
+    NoKey,
+    #[error("invalid key file {path}: {message}")]
+    Key { path: String, message: String },
+    #[error("failed to decrypt value: {0}")]
+    Decrypt(String),
+}
+
+/// 密码等敏感配置，`Debug` 和序列化时输出 `***`
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Secret {
+        Secret(value.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", REDACTED)
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(REDACTED)
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Secret, D::Error> {
+        String::deserialize(deserializer).map(Secret)
+    }
+}
+
+/// 解析配置值中的密钥引用：
+///
+/// - `${env:FLINK_PASSWORD}`：环境变量
+/// - `${file:/run/secrets/flink}`：文件内容，去掉末尾的换行
+/// - `enc:...`：用本地密钥文件解密的 AES-256-GCM 密文，由 `flink_cli config encrypt` 生成
+///
+/// `${...}` 可以出现在值的任意位置，`enc:` 必须是整个值。
+#[derive(Debug, Clone, Default)]
+pub struct SecretResolver {
+    key_file: Option<PathBuf>,
+}
+
+impl SecretResolver {
+    pub fn new(key_file: Option<PathBuf>) -> SecretResolver {
+        SecretResolver { key_file }
+    }
+
+    /// 密钥文件取自 `APP_KEY_FILE`，未设置时使用 `~/.config/rust_project/secret.key`
+    pub fn from_env() -> SecretResolver {
+        let key_file = env::var_os(KEY_FILE_ENV).map(PathBuf::from).or_else(|| {
+            env::var_os("HOME").map(|home| {
+                PathBuf::from(home)
+                    .join(".config")
+                    .join("rust_project")
+                    .join("secret.key")
+            })
+        });
+        SecretResolver { key_file }
+    }
+
+    pub fn resolve(&self, value: &str) -> Result<String, SecretError> {
+        if let Some(encrypted) = value.strip_prefix(ENCRYPTED_PREFIX) {
+            return decrypt(&self.key()?, encrypted);
+        }
+        let mut error = None;
+        let resolved = REFERENCE.replace_all(value, |captures: &Captures| {
+            let name = captures[2].trim();
+            let result = if &captures[1] == "env" {
+                env::var(name).map_err(|_| SecretError::MissingEnv(name.to_string()))
+            } else {
+                fs::read_to_string(name)
+                    .map(|content| content.trim_end_matches(['\r', '\n']).to_string())
+                    .map_err(|source| SecretError::File {
+                        path: name.to_string(),
+                        source,
+                    })
+            };
+            result.unwrap_or_else(|e| {
+                error.get_or_insert(e);
+                String::new()
+            })
+        });
+        match error {
+            Some(e) => Err(e),
+            None => Ok(resolved.into_owned()),
+        }
+    }
+
+    /// 解析表中所有字符串值，出错时返回配置项名称
+    pub fn resolve_table(&self, table: &mut Table) -> Result<(), (String, SecretError)> {
+        for (key, value) in table.iter_mut() {
+            self.resolve_value(key, value)?;
+        }
+        Ok(())
+    }
+
+    fn resolve_value(&self, key: &str, value: &mut Value) -> Result<(), (String, SecretError)> {
+        match value {
+            Value::String(text) => {
+                *text = self.resolve(text).map_err(|e| (key.to_string(), e))?;
+            }
+            Value::Array(values) => {
+                for value in values {
+                    self.resolve_value(key, value)?;
+                }
+            }
+            Value::Table(table) => {
+                for (name, value) in table.iter_mut() {
+                    self.resolve_value(&format!("{}.{}", key, name), value)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn key(&self) -> Result<[u8; 32], SecretError> {
+        let path = self.key_file.as_deref().ok_or(SecretError::NoKey)?;
+        read_key(path)
+    }
+}
+
+fn read_key(path: &Path) -> Result<[u8; 32], SecretError> {
+    let key_error = |message: String| SecretError::Key {
+        path: path.display().to_string(),
+        message,
+    };
+    let content = fs::read_to_string(path).map_err(|e| key_error(e.to_string()))?;
+    BASE64_STANDARD
+        .decode(content.trim())
+        .map_err(|e| key_error(e.to_string()))?
+        .try_into()
+        .map_err(|_| key_error(String::from("expected 32 base64 encoded bytes")))
+}
+
+/// 生成新的密钥，内容为 base64 编码的 32 字节
+pub fn generate_key() -> String {
+    let mut key = [0u8; 32];
+    SystemRandom::new()
+        .fill(&mut key)
+        .expect("system random generator is available");
+    BASE64_STANDARD.encode(key)
+}
+
+/// 加密后的值带有 `enc:` 前缀，可以直接写入配置文件
+pub fn encrypt(key: &[u8; 32], plaintext: &str) -> String {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .expect("system random generator is available");
+    let mut data = plaintext.as_bytes().to_vec();
+    cipher(key)
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+        .expect("plaintext fits in a single AES-GCM message");
+    let mut payload = nonce.to_vec();
+    payload.extend(data);
+    format!("{}{}", ENCRYPTED_PREFIX, BASE64_STANDARD.encode(payload))
+}
+
+fn decrypt(key: &[u8; 32], encrypted: &str) -> Result<String, SecretError> {
+    let payload = BASE64_STANDARD
+        .decode(encrypted.trim())
+        .map_err(|e| SecretError::Decrypt(e.to_string()))?;
+    if payload.len() < NONCE_LEN {
+        return Err(SecretError::Decrypt(String::from("value is too short")));
+    }
+    let (nonce, data) = payload.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce)
+        .map_err(|_| SecretError::Decrypt(String::from("invalid nonce")))?;
+    let mut data = data.to_vec();
+    let plaintext = cipher(key)
+        .open_in_place(nonce, Aad::empty(), &mut data)
+        .map_err(|_| SecretError::Decrypt(String::from("wrong key or corrupted value")))?;
+    String::from_utf8(plaintext.to_vec()).map_err(|e| SecretError::Decrypt(e.to_string()))
+}
+
+fn cipher(key: &[u8; 32]) -> LessSafeKey {
+    LessSafeKey::new(UnboundKey::new(&AES_256_GCM, key).expect("key has the AES-256 length"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let dir = env::temp_dir().join(format!("rust_project_secret_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let secret_file = dir.join("flink");
+        fs::write(&secret_file, "s3cret\n").unwrap();
+        let key_file = dir.join("secret.key");
+        fs::write(&key_file, generate_key()).unwrap();
+        let resolver = SecretResolver::new(Some(key_file));
+
+        let value = format!("${{file:{}}}", secret_file.display());
+        assert_eq!(resolver.resolve(&value).unwrap(), "s3cret");
+        assert_eq!(
+            resolver.resolve("home=${env:PATH}").unwrap(),
+            format!("home={}", env::var("PATH").unwrap())
+        );
+        assert!(matches!(
+            resolver.resolve("${env:RUST_PROJECT_UNSET_VARIABLE}"),
+            Err(SecretError::MissingEnv(_))
+        ));
+
+        let encrypted = encrypt(&resolver.key().unwrap(), "p@ss");
+        assert_eq!(resolver.resolve(&encrypted).unwrap(), "p@ss");
+        let other = SecretResolver::new(Some(dir.join("other.key")));
+        fs::write(dir.join("other.key"), generate_key()).unwrap();
+        assert!(matches!(
+            other.resolve(&encrypted),
+            Err(SecretError::Decrypt(_))
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_redacted() {
+        let secret = Secret::new("p@ss");
+        assert_eq!(format!("{:?}", Some(secret.clone())), "Some(\"***\")");
+        assert_eq!(secret.expose(), "p@ss");
+    }
+}
@@ -0,0 +1,146 @@
+use crate::config::LoadedConfig;
+use crate::config::layered::flatten;
+use std::fmt;
+use toml::{Table, Value};
+
+/// 生效配置中的一项：名称、取值（密码显示为 `***`）和来源
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub key: String,
+    pub value: Value,
+    pub origin: String,
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {}  # {}", self.key, self.value, self.origin)
+    }
+}
+
+impl LoadedConfig {
+    /// 合并后生效的 `[app]`/`[search]` 配置，密钥引用已解析，按名称排序
+    pub fn effective(&self) -> Vec<Entry> {
+        let table = Table::try_from(&self.config).expect("config serializes to TOML");
+        entries("", table, |key| self.origin(key))
+    }
+
+    /// 由其它模块读取的部分，例如 [`crate::client::flink::FlinkConfig`] 读取的 `[flink]`，
+    /// `table` 为该部分生效的值，未写出的配置项标注为默认值
+    pub fn section_entries(&self, name: &str, table: Table) -> Vec<Entry> {
+        entries(name, table, |key| self.origin(key))
+    }
+}
+
+/// 展开为配置项，表数组按元素展开为 `flink.clusters[0].name`
+fn entries(prefix: &str, table: Table, origin: impl Fn(&str) -> String) -> Vec<Entry> {
+    let mut leaves = Vec::new();
+    flatten(prefix, Value::Table(table), &mut leaves);
+    let mut expanded = Vec::new();
+    for (key, value) in leaves {
+        match value {
+            Value::Array(items) if !items.is_empty() && items.iter().all(Value::is_table) => {
+                for (index, item) in items.into_iter().enumerate() {
+                    flatten(&format!("{}[{}]", key, index), item, &mut expanded);
+                }
+            }
+            value => expanded.push((key, value)),
+        }
+    }
+    expanded
+        .into_iter()
+        .map(|(key, value)| Entry {
+            origin: origin(&key),
+            key,
+            value,
+        })
+        .collect()
+}
+
+/// 表数组作为一个整体合并，元素中的配置项使用数组的来源，`flink.clusters[0].name` 对应 `flink.clusters`
+pub(super) fn array_key(key: &str) -> &str {
+    key.split('[').next().unwrap_or(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::flink::FlinkConfig;
+    use crate::config::{ConfigLoader, Layer};
+    use std::{env, fs};
+
+    #[test]
+    fn test_effective() {
+        let dir = env::temp_dir().join(format!("rust_project_show_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        fs::write(
+            &path,
+            "version = 2\n\n[app]\nport = 8080\n\n[search]\nusername = \"es\"\npassword = \"p@ss\"\n\n[[flink.clusters]]\nname = \"prod\"\nurl = \"http://jm:8081\"\n",
+        )
+        .unwrap();
+        let loaded = ConfigLoader::new(path.to_str().unwrap())
+            .with_file(Layer::System, dir.join("system.toml"))
+            .with_file(Layer::User, dir.join("user.toml"))
+            .with_env(vec![(
+                String::from("APP_APP__PAGE_SIZE"),
+                String::from("20"),
+            )])
+            .with_overrides(vec![String::from(
+                "flink.naming.patterns=['^(?P<team>[a-z]+)-']",
+            )])
+            .load()
+            .unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            loaded.get("search.password"),
+            Some(Value::String(String::from("***")))
+        );
+        assert!(!loaded.get("search").unwrap().to_string().contains("p@ss"));
+
+        let lines: Vec<String> = loaded
+            .effective()
+            .iter()
+            .map(|entry| entry.to_string())
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                String::from("app.page_size = 20  # environment variable APP_APP__PAGE_SIZE"),
+                format!("app.port = 8080  # local file {}:4", path.display()),
+                String::from("search.nodes = [\"http://localhost:9200\"]  # built-in default"),
+                format!(
+                    "search.password = \"***\"  # local file {}:8",
+                    path.display()
+                ),
+                format!(
+                    "search.username = \"es\"  # local file {}:7",
+                    path.display()
+                ),
+            ]
+        );
+
+        // [flink] 同样参与分层合并
+        let flink = FlinkConfig::from_loaded(&loaded).unwrap();
+        assert_eq!(flink.clusters[0].name, "prod");
+        assert_eq!(flink.naming.patterns, vec!["^(?P<team>[a-z]+)-"]);
+        let entries = loaded.section_entries("flink", Table::try_from(flink).unwrap());
+        let origin = |key: &str| {
+            entries
+                .iter()
+                .find(|entry| entry.key == key)
+                .map(|entry| entry.origin.clone())
+        };
+        assert_eq!(
+            origin("flink.clusters[0].name"),
+            Some(format!("local file {}:11", path.display()))
+        );
+        assert_eq!(
+            origin("flink.clusters[0].timeout_secs").as_deref(),
+            Some("built-in default")
+        );
+        assert_eq!(
+            origin("flink.naming.patterns").as_deref(),
+            Some("--set flink.naming.patterns=['^(?P<team>[a-z]+)-']")
+        );
+    }
+}
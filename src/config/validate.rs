@@ -0,0 +1,158 @@
+use crate::config::features::DEFAULT_ENVIRONMENT;
+use crate::config::{Config, Rollout, Toggle};
+use reqwest::Url;
+use std::fmt;
+
+/// 每页条数的上限，超过时搜索请求会被 Elasticsearch 拒绝或者非常慢
+pub const MAX_PAGE_SIZE: i32 = 1000;
+
+/// 校验发现的问题，`key` 为配置项名称
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    pub key: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.key, self.message)
+    }
+}
+
+impl Config {
+    /// 检查取值范围、URL 格式和配置项之间的约束，返回所有问题
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        let mut check = |ok: bool, key: &str, message: String| {
+            if !ok {
+                issues.push(ValidationIssue {
+                    key: key.to_string(),
+                    message,
+                });
+            }
+        };
+
+        check(
+            (1..=65535).contains(&self.app.port),
+            "app.port",
+            format!("{} is not a port between 1 and 65535", self.app.port),
+        );
+        check(
+            (1..=MAX_PAGE_SIZE).contains(&self.app.page_size),
+            "app.page_size",
+            format!(
+                "{} must be between 1 and {}",
+                self.app.page_size, MAX_PAGE_SIZE
+            ),
+        );
+
+        check(
+            self.search.password.is_none() || self.search.username.is_some(),
+            "search.password",
+            String::from("is set but search.username is not"),
+        );
+
+        check(
+            !self.search.nodes.is_empty(),
+            "search.nodes",
+            String::from("at least one node is required"),
+        );
+        for node in &self.search.nodes {
+            match Url::parse(node) {
+                Ok(url) if url.scheme() != "http" && url.scheme() != "https" => check(
+                    false,
+                    "search.nodes",
+                    format!("{}: scheme {} is not http or https", node, url.scheme()),
+                ),
+                Ok(url) => {
+                    // 搜索服务与应用在同一台机器上时不能使用同一个端口
+                    let local = matches!(
+                        url.host_str(),
+                        Some("localhost" | "127.0.0.1" | "0.0.0.0" | "[::1]")
+                    );
+                    check(
+                        !(local
+                            && url.port_or_known_default() == u16::try_from(self.app.port).ok()),
+                        "search.nodes",
+                        format!("{} uses app.port {} on the same host", node, self.app.port),
+                    );
+                }
+                Err(e) => check(
+                    false,
+                    "search.nodes",
+                    format!("{:?} is not a valid URL: {}", node, e),
+                ),
+            }
+        }
+        for (name, toggle) in &self.features {
+            let rollouts: Vec<(String, Rollout)> = match toggle {
+                Toggle::Fixed(rollout) => vec![(format!("features.{}", name), *rollout)],
+                Toggle::PerEnvironment(values) => {
+                    check(
+                        values.contains_key(DEFAULT_ENVIRONMENT),
+                        &format!("features.{}", name),
+                        format!(
+                            "has no {} value, other environments are disabled",
+                            DEFAULT_ENVIRONMENT
+                        ),
+                    );
+                    values
+                        .iter()
+                        .map(|(environment, rollout)| {
+                            (format!("features.{}.{}", name, environment), *rollout)
+                        })
+                        .collect()
+                }
+            };
+            for (key, rollout) in rollouts {
+                if let Rollout::Percent(percent) = rollout {
+                    check(
+                        percent <= 100,
+                        &key,
+                        format!("rollout {}% is above 100%", percent),
+                    );
+                }
+            }
+        }
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(Config::default().validate().is_empty());
+
+        let mut config = Config::default();
+        config.app.port = -1;
+        config.app.page_size = 0;
+        config.search.nodes = vec![String::from("localhost:9200")];
+        let keys: Vec<String> = config
+            .validate()
+            .into_iter()
+            .map(|issue| issue.key)
+            .collect();
+        assert_eq!(keys, vec!["app.port", "app.page_size", "search.nodes"]);
+
+        config = Config::default();
+        config.features.insert(
+            String::from("search_indexing"),
+            Toggle::Fixed(Rollout::Percent(150)),
+        );
+        assert_eq!(
+            config.validate()[0].to_string(),
+            "features.search_indexing: rollout 150% is above 100%"
+        );
+
+        config = Config::default();
+        config.app.port = 9200;
+        let issues = config.validate();
+        assert_eq!(
+            issues[0].to_string(),
+            "search.nodes: http://localhost:9200 uses app.port 9200 on the same host"
+        );
+    }
+}
@@ -0,0 +1,268 @@
+use crate::config::layered::flatten;
+use crate::config::schema;
+use crate::config::secret::REDACTED;
+use crate::config::{Config, ConfigLoader, FeatureFlags, LoadedConfig};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle, sleep};
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+
+/// 配置项的变化，`None` 表示新增或删除
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub key: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secret = schema::is_secret(&self.key);
+        let show = |value: &Option<String>| match value {
+            Some(_) if secret => format!("{:?}", REDACTED),
+            Some(value) => value.clone(),
+            None => String::from("(unset)"),
+        };
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.key,
+            show(&self.old),
+            show(&self.new)
+        )
+    }
+}
+
+/// 一次检查的结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reload {
+    /// 文件没有变化
+    Unchanged,
+    /// 文件变化但合并后的配置相同
+    Same,
+    /// 新配置已发布给订阅者
+    Applied(Vec<Change>),
+    /// 新配置无效，继续使用旧配置
+    Rejected(Vec<String>),
+}
+
+/// 轮询配置文件的修改时间，重新加载并校验后通过 watch channel 发布新配置
+///
+/// 订阅者用 [`watch::Receiver::has_changed`] 或 `changed().await` 得知配置变化，
+/// 用 `borrow_and_update()` 取得最新配置。
+pub struct ConfigWatcher {
+    loader: ConfigLoader,
+    current: LoadedConfig,
+    stamps: Vec<(PathBuf, Option<(SystemTime, u64)>)>,
+    sender: watch::Sender<Arc<Config>>,
+}
+
+impl ConfigWatcher {
+    /// 第一次加载的配置必须有效
+    pub fn new(loader: ConfigLoader) -> Result<ConfigWatcher, Vec<String>> {
+        let current = load_valid(&loader)?;
+        let (sender, _) = watch::channel(Arc::new(current.config.clone()));
+        let stamps = stamps(&loader);
+        Ok(ConfigWatcher {
+            loader,
+            current,
+            stamps,
+            sender,
+        })
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<Arc<Config>> {
+        self.sender.subscribe()
+    }
+
+    /// 跟随本 watcher 热更新的功能开关，环境为 loader 的 profile
+    pub fn features(&self) -> FeatureFlags {
+        FeatureFlags::new(self.subscribe(), self.loader.profile().map(String::from))
+    }
+
+    pub fn current(&self) -> &LoadedConfig {
+        &self.current
+    }
+
+    /// 检查一次文件是否变化，变化时重新加载
+    pub fn check(&mut self) -> Reload {
+        let stamps = stamps(&self.loader);
+        if stamps == self.stamps {
+            return Reload::Unchanged;
+        }
+        self.stamps = stamps;
+        let loaded = match load_valid(&self.loader) {
+            Ok(loaded) => loaded,
+            Err(problems) => return Reload::Rejected(problems),
+        };
+        let changes = diff(&self.current, &loaded);
+        if changes.is_empty() {
+            return Reload::Same;
+        }
+        self.sender.send_replace(Arc::new(loaded.config.clone()));
+        self.current = loaded;
+        Reload::Applied(changes)
+    }
+
+    /// 在后台线程中每隔 `interval` 检查一次并把结果打印到 stderr，所有订阅者都释放后退出
+    pub fn spawn(mut self, interval: Duration) -> JoinHandle<()> {
+        thread::spawn(move || {
+            while !self.sender.is_closed() {
+                sleep(interval);
+                match self.check() {
+                    Reload::Unchanged | Reload::Same => {}
+                    Reload::Applied(changes) => {
+                        eprintln!("config reloaded:");
+                        for change in changes {
+                            eprintln!("  {}", change);
+                        }
+                    }
+                    Reload::Rejected(problems) => {
+                        eprintln!("config change rejected, keeping the previous config:");
+                        for problem in problems {
+                            eprintln!("  {}", problem);
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+fn load_valid(loader: &ConfigLoader) -> Result<LoadedConfig, Vec<String>> {
+    let loaded = loader.load().map_err(|e| vec![e.to_string()])?;
+    let issues = loaded.config.validate();
+    if !issues.is_empty() {
+        return Err(issues.iter().map(|issue| issue.to_string()).collect());
+    }
+    Ok(loaded)
+}
+
+/// 所有配置文件的修改时间和大小，不存在的文件为 `None`
+fn stamps(loader: &ConfigLoader) -> Vec<(PathBuf, Option<(SystemTime, u64)>)> {
+    loader
+        .files()
+        .into_iter()
+        .map(|(_, path)| {
+            let stamp = fs::metadata(&path)
+                .ok()
+                .and_then(|meta| Some((meta.modified().ok()?, meta.len())));
+            (path, stamp)
+        })
+        .collect()
+}
+
+/// 比较原始值，输出的 `old`/`new` 中密码等敏感值替换为 `***`，只显示发生了变化
+pub fn diff(old: &LoadedConfig, new: &LoadedConfig) -> Vec<Change> {
+    let values = |loaded: &LoadedConfig| {
+        let mut leaves = Vec::new();
+        flatten("", toml::Value::Table(loaded.table.clone()), &mut leaves);
+        leaves
+            .into_iter()
+            .map(|(key, value)| {
+                let raw = value.to_string();
+                let mut shown = value;
+                schema::redact(&key, &mut shown);
+                (key, (raw, shown.to_string()))
+            })
+            .collect::<BTreeMap<String, (String, String)>>()
+    };
+    let (old, new) = (values(old), values(new));
+    let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    let raw = |values: &BTreeMap<String, (String, String)>, key: &str| {
+        values.get(key).map(|(raw, _)| raw.clone())
+    };
+    let shown = |values: &BTreeMap<String, (String, String)>, key: &str| {
+        values.get(key).map(|(_, shown)| shown.clone())
+    };
+    keys.into_iter()
+        .filter(|key| raw(&old, key) != raw(&new, key))
+        .map(|key| Change {
+            key: key.clone(),
+            old: shown(&old, key),
+            new: shown(&new, key),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Layer;
+    use std::env;
+    use std::fs::File;
+
+    #[test]
+    fn test_reload() {
+        let dir = env::temp_dir().join(format!("rust_project_watch_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        let write = |content: &str, age_secs: u64| {
+            fs::write(&path, content).unwrap();
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(SystemTime::now() - Duration::from_secs(age_secs))
+                .unwrap();
+        };
+        write("[app]\nport = 8080\n", 30);
+        let loader = ConfigLoader::new(path.to_str().unwrap())
+            .with_file(Layer::System, dir.join("system.toml"))
+            .with_file(Layer::User, dir.join("user.toml"))
+            .with_env(Vec::new());
+        let mut watcher = ConfigWatcher::new(loader).unwrap();
+        let mut receiver = watcher.subscribe();
+        assert_eq!(watcher.check(), Reload::Unchanged);
+
+        write("[app]\nport = 0\n", 20);
+        assert!(matches!(watcher.check(), Reload::Rejected(_)));
+        assert!(!receiver.has_changed().unwrap());
+        assert_eq!(watcher.current().config.app.port, 8080);
+
+        write("[app]\nport = 9090\npage_size = 25\n", 10);
+        let reload = watcher.check();
+        assert_eq!(
+            reload,
+            Reload::Applied(vec![
+                Change {
+                    key: String::from("app.page_size"),
+                    old: Some(String::from("10")),
+                    new: Some(String::from("25")),
+                },
+                Change {
+                    key: String::from("app.port"),
+                    old: Some(String::from("8080")),
+                    new: Some(String::from("9090")),
+                },
+            ])
+        );
+        assert!(receiver.has_changed().unwrap());
+        assert_eq!(receiver.borrow_and_update().app.port, 9090);
+
+        let secret = "[app]\nport = 9090\npage_size = 25\n[search]\nusername = \"es\"\n";
+        write(&format!("{}password = \"hunter2\"\n", secret), 5);
+        watcher.check();
+        write(&format!("{}password = \"newpass\"\n", secret), 1);
+        let reload = watcher.check();
+        fs::remove_dir_all(&dir).unwrap();
+        let Reload::Applied(changes) = reload else {
+            panic!("unexpected {:?}", reload);
+        };
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            changes[0].to_string(),
+            "search.password: \"***\" -> \"***\""
+        );
+        assert_eq!(
+            receiver.borrow().search.password.as_ref().unwrap().expose(),
+            "newpass"
+        );
+    }
+}
@@ -0,0 +1,284 @@
+use crate::client::error::FlinkError;
+use crate::client::flink::FlinkClient;
+use crate::models::flink::JobDetail;
+use crate::models::plan::JobPlan;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+const MAX_LABEL_LEN: usize = 80;
+
+/// 作业的算子 DAG，节点带有并行度、当前状态和反压等级
+#[derive(Debug, Clone)]
+pub struct JobGraph {
+    pub name: String,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GraphNode {
+    pub id: String,
+    pub label: String,
+    pub parallelism: i32,
+    pub status: Option<String>,
+    pub backpressure: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    pub ship_strategy: String,
+}
+
+impl JobGraph {
+    /// `detail` 提供顶点名称和状态，`backpressure` 为顶点 id 到反压等级的映射
+    pub fn from_plan(
+        plan: &JobPlan,
+        detail: Option<&JobDetail>,
+        backpressure: &BTreeMap<String, String>,
+    ) -> JobGraph {
+        let nodes = plan
+            .nodes
+            .iter()
+            .map(|node| {
+                let vertex = detail.and_then(|d| d.vertices.iter().find(|v| v.id == node.id));
+                let name = vertex.map(|v| v.name.as_str()).unwrap_or(&node.description);
+                GraphNode {
+                    id: node.id.clone(),
+                    label: clean_label(name),
+                    parallelism: node.parallelism,
+                    status: vertex.map(|v| v.status.clone()),
+                    backpressure: backpressure.get(&node.id).cloned(),
+                }
+            })
+            .collect();
+        let edges = plan
+            .nodes
+            .iter()
+            .flat_map(|node| {
+                node.inputs.iter().map(|input| GraphEdge {
+                    from: input.id.clone(),
+                    to: node.id.clone(),
+                    ship_strategy: input.ship_strategy.clone(),
+                })
+            })
+            .collect();
+        JobGraph {
+            name: plan.name.clone(),
+            nodes,
+            edges,
+        }
+    }
+
+    /// 拉取 plan、作业详情，以及（可选）每个顶点的反压采样
+    pub fn fetch(
+        client: &FlinkClient,
+        jid: &str,
+        with_backpressure: bool,
+    ) -> Result<JobGraph, FlinkError> {
+        let plan = client.job_plan(jid)?;
+        let detail = client.job_detail(jid).ok();
+        let mut backpressure = BTreeMap::new();
+        if with_backpressure {
+            for node in &plan.nodes {
+                // 反压采样是异步的，第一次请求可能还没有结果
+                if let Ok(info) = client.vertex_backpressure(jid, &node.id)
+                    && let Some(level) = info.backpressure_level
+                {
+                    backpressure.insert(node.id.clone(), level);
+                }
+            }
+        }
+        Ok(JobGraph::from_plan(&plan, detail.as_ref(), &backpressure))
+    }
+
+    fn annotation(node: &GraphNode) -> String {
+        let mut parts = vec![format!("parallelism: {}", node.parallelism)];
+        if let Some(status) = &node.status {
+            parts.push(status.clone());
+        }
+        if let Some(level) = &node.backpressure {
+            parts.push(format!("backpressure: {}", level));
+        }
+        parts.join(", ")
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "digraph \"{}\" {{", escape_dot(&self.name));
+        out.push_str("  rankdir=LR;\n");
+        out.push_str("  node [shape=box, style=\"rounded,filled\", fontname=\"Helvetica\"];\n");
+        for node in &self.nodes {
+            let _ = writeln!(
+                out,
+                "  \"{}\" [label=\"{}\\n{}\", fillcolor=\"{}\", color=\"{}\"];",
+                node.id,
+                escape_dot(&node.label),
+                escape_dot(&Self::annotation(node)),
+                backpressure_color(node.backpressure.as_deref()),
+                status_color(node.status.as_deref()),
+            );
+        }
+        for edge in &self.edges {
+            let _ = writeln!(
+                out,
+                "  \"{}\" -> \"{}\" [label=\"{}\"];",
+                edge.from,
+                edge.to,
+                escape_dot(&edge.ship_strategy)
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Mermaid 不允许十六进制 id 作为节点名，这里使用 `n0`、`n1` 等别名
+    pub fn to_mermaid(&self) -> String {
+        let alias: BTreeMap<&str, String> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node.id.as_str(), format!("n{}", i)))
+            .collect();
+        let mut out = String::from("flowchart LR\n");
+        for node in &self.nodes {
+            let _ = writeln!(
+                out,
+                "  {}[\"{}<br/>{}\"]",
+                alias[node.id.as_str()],
+                escape_mermaid(&node.label),
+                escape_mermaid(&Self::annotation(node))
+            );
+        }
+        for edge in &self.edges {
+            let (Some(from), Some(to)) =
+                (alias.get(edge.from.as_str()), alias.get(edge.to.as_str()))
+            else {
+                continue;
+            };
+            if edge.ship_strategy.is_empty() {
+                let _ = writeln!(out, "  {} --> {}", from, to);
+            } else {
+                let _ = writeln!(
+                    out,
+                    "  {} -->|{}| {}",
+                    from,
+                    escape_mermaid(&edge.ship_strategy),
+                    to
+                );
+            }
+        }
+        for level in ["ok", "low", "high"] {
+            let ids: Vec<&str> = self
+                .nodes
+                .iter()
+                .filter(|node| node.backpressure.as_deref() == Some(level))
+                .map(|node| alias[node.id.as_str()].as_str())
+                .collect();
+            if ids.is_empty() {
+                continue;
+            }
+            let _ = writeln!(
+                out,
+                "  classDef bp_{} fill:{}",
+                level,
+                backpressure_color(Some(level))
+            );
+            let _ = writeln!(out, "  class {} bp_{}", ids.join(","), level);
+        }
+        out
+    }
+}
+
+/// plan 中的描述带有 `<br/>`、缩进和很长的字段列表，只保留一行摘要
+fn clean_label(description: &str) -> String {
+    let text = description.replace("<br/>", " ");
+    let text: Vec<&str> = text.split_whitespace().collect();
+    let text = text.join(" ");
+    if text.chars().count() <= MAX_LABEL_LEN {
+        return text;
+    }
+    let truncated: String = text.chars().take(MAX_LABEL_LEN - 3).collect();
+    format!("{}...", truncated)
+}
+
+fn backpressure_color(level: Option<&str>) -> &'static str {
+    match level {
+        Some("ok") => "#d4edda",
+        Some("low") => "#fff3cd",
+        Some("high") => "#f8d7da",
+        _ => "#ffffff",
+    }
+}
+
+fn status_color(status: Option<&str>) -> &'static str {
+    match status {
+        Some("RUNNING") => "#28a745",
+        Some("FAILED") | Some("CANCELING") => "#dc3545",
+        Some("FINISHED") => "#007bff",
+        _ => "#6c757d",
+    }
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_mermaid(text: &str) -> String {
+    text.replace('"', "#quot;")
+        .replace('|', "#124;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> JobGraph {
+        let plan: JobPlan = serde_json::from_str(
+            r#"{
+                "jid": "j1",
+                "name": "orders \"etl\"",
+                "nodes": [
+                    {"id": "b2", "parallelism": 4, "operator": "", "description": "Sink: Print",
+                     "inputs": [{"num": 0, "id": "a1", "ship_strategy": "HASH", "exchange": "pipelined"}]},
+                    {"id": "a1", "parallelism": 2, "operator": "",
+                     "description": "Source: Kafka<br/>  -> Map<br/>"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let backpressure = BTreeMap::from([("a1".to_string(), "high".to_string())]);
+        JobGraph::from_plan(&plan, None, &backpressure)
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let dot = graph().to_dot();
+        assert!(dot.starts_with("digraph \"orders \\\"etl\\\"\" {"));
+        assert!(dot.contains(
+            "\"a1\" [label=\"Source: Kafka -> Map\\nparallelism: 2, backpressure: high\", \
+             fillcolor=\"#f8d7da\""
+        ));
+        assert!(dot.contains("\"a1\" -> \"b2\" [label=\"HASH\"];"));
+    }
+
+    #[test]
+    fn test_to_mermaid() {
+        let mermaid = graph().to_mermaid();
+        assert!(
+            mermaid
+                .contains("n1[\"Source: Kafka -#gt; Map<br/>parallelism: 2, backpressure: high\"]")
+        );
+        assert!(mermaid.contains("n1 -->|HASH| n0"));
+        assert!(mermaid.contains("class n1 bp_high"));
+    }
+
+    #[test]
+    fn test_clean_label() {
+        let long = "x".repeat(100);
+        assert_eq!(clean_label(&long).chars().count(), MAX_LABEL_LEN);
+    }
+}
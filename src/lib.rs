@@ -1,6 +1,20 @@
 // mod models{}
 // mod  models; models.rs 常用的方式
 //mod  models; models/mod.rs
+pub mod capacity;
+pub mod checkpoint_trend;
+pub mod client;
+pub mod config;
+pub mod graph;
+pub mod lint;
+pub mod logs;
 pub mod models;
+pub mod naming;
+pub mod operator;
+pub mod reconcile;
+pub mod sla;
+pub mod snapshot;
+pub mod status_log;
+pub mod storm;
 #[cfg(test)]
 pub mod test;
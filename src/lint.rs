@@ -0,0 +1,477 @@
+use crate::operator::SAVEPOINT_DIR_KEYS;
+use crate::reconcile::{DesiredJob, Manifest, SavepointPolicy};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+const CHECKPOINT_INTERVAL: &str = "execution.checkpointing.interval";
+const CHECKPOINT_TIMEOUT: &str = "execution.checkpointing.timeout";
+const CHECKPOINT_MIN_PAUSE: &str = "execution.checkpointing.min-pause";
+const CHECKPOINT_DIR_KEYS: [&str; 2] = ["state.checkpoints.dir", "execution.checkpointing.dir"];
+const RESTART_STRATEGY_KEYS: [&str; 2] = ["restart-strategy.type", "restart-strategy"];
+const STATE_BACKEND_KEYS: [&str; 2] = ["state.backend.type", "state.backend"];
+const DEFAULT_PARALLELISM: &str = "parallelism.default";
+const MAX_PARALLELISM: &str = "pipeline.max-parallelism";
+/// Flink 允许的最大并行度上限
+const MAX_PARALLELISM_LIMIT: i32 = 32768;
+
+/// 配置检查规则，对应 config.toml 中的 `[flink.lint]`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LintRules {
+    /// checkpoint 间隔小于该值时给出警告
+    #[serde(default = "default_min_checkpoint_interval_secs")]
+    pub min_checkpoint_interval_secs: u64,
+    /// 允许使用的 state backend
+    #[serde(default = "default_state_backends")]
+    pub state_backends: Vec<String>,
+    /// 是否要求显式设置 `pipeline.max-parallelism`
+    #[serde(default = "default_require_max_parallelism")]
+    pub require_max_parallelism: bool,
+}
+
+fn default_min_checkpoint_interval_secs() -> u64 {
+    10
+}
+
+fn default_state_backends() -> Vec<String> {
+    vec![String::from("hashmap"), String::from("rocksdb")]
+}
+
+fn default_require_max_parallelism() -> bool {
+    true
+}
+
+impl Default for LintRules {
+    fn default() -> Self {
+        LintRules {
+            min_checkpoint_interval_secs: default_min_checkpoint_interval_secs(),
+            state_backends: default_state_backends(),
+            require_max_parallelism: default_require_max_parallelism(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// 检查发现的问题，`key` 为相关的 Flink 配置项
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintIssue {
+    pub job: String,
+    pub severity: Severity,
+    pub key: String,
+    pub message: String,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<7} {}: {}: {}",
+            self.severity, self.job, self.key, self.message
+        )
+    }
+}
+
+/// 解析 Flink 的时长配置，例如 `500`、`30 s`、`1min`、`2h`，没有单位时为毫秒
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let amount: u64 = value[..split].parse().ok()?;
+    let millis = match value[split..].trim().to_lowercase().as_str() {
+        "" | "ms" | "milli" | "millis" | "millisecond" | "milliseconds" => 1,
+        "s" | "sec" | "secs" | "second" | "seconds" => 1000,
+        "m" | "min" | "mins" | "minute" | "minutes" => 60_000,
+        "h" | "hour" | "hours" => 3_600_000,
+        "d" | "day" | "days" => 86_400_000,
+        _ => return None,
+    };
+    Some(Duration::from_millis(amount.checked_mul(millis)?))
+}
+
+/// 作业配置覆盖集群配置后的生效配置
+struct Effective<'a> {
+    job: &'a BTreeMap<String, String>,
+    cluster: &'a BTreeMap<String, String>,
+}
+
+impl<'a> Effective<'a> {
+    /// 返回第一个设置了的配置项及其值
+    fn get(&self, keys: &[&'a str]) -> Option<(&'a str, &'a str)> {
+        keys.iter().find_map(|key| {
+            self.job
+                .get(*key)
+                .or_else(|| self.cluster.get(*key))
+                .map(|value| (*key, value.trim()))
+        })
+    }
+}
+
+struct Linter<'a> {
+    job: &'a DesiredJob,
+    config: Effective<'a>,
+    rules: &'a LintRules,
+    issues: Vec<LintIssue>,
+}
+
+impl Linter<'_> {
+    fn report(&mut self, severity: Severity, key: &str, message: String) {
+        self.issues.push(LintIssue {
+            job: self.job.name.clone(),
+            severity,
+            key: key.to_string(),
+            message,
+        });
+    }
+
+    fn duration(&mut self, key: &str) -> Option<Duration> {
+        let (key, value) = self.config.get(&[key])?;
+        let duration = parse_duration(value);
+        if duration.is_none() {
+            self.report(
+                Severity::Error,
+                key,
+                format!("{:?} is not a duration such as 500ms, 30s or 1min", value),
+            );
+        }
+        duration
+    }
+
+    fn checkpointing(&mut self) -> Option<Duration> {
+        if self.config.get(&[CHECKPOINT_INTERVAL]).is_none() {
+            self.report(
+                Severity::Warning,
+                CHECKPOINT_INTERVAL,
+                String::from(
+                    "checkpointing is disabled, after a failure the job restarts without state",
+                ),
+            );
+            return None;
+        }
+        let interval = self.duration(CHECKPOINT_INTERVAL)?;
+        let min_interval = Duration::from_secs(self.rules.min_checkpoint_interval_secs);
+        if interval < min_interval {
+            self.report(
+                Severity::Warning,
+                CHECKPOINT_INTERVAL,
+                format!(
+                    "interval {:?} is below {:?}, frequent checkpoints compete with processing for I/O",
+                    interval, min_interval
+                ),
+            );
+        }
+        // 未设置时 Flink 默认 10 分钟
+        let timeout = self
+            .duration(CHECKPOINT_TIMEOUT)
+            .unwrap_or(Duration::from_secs(600));
+        if timeout < interval {
+            self.report(
+                Severity::Warning,
+                CHECKPOINT_TIMEOUT,
+                format!(
+                    "timeout {:?} is shorter than the interval {:?}, a slow checkpoint is aborted \
+                     and the job goes a whole interval without a successful one",
+                    timeout, interval
+                ),
+            );
+        }
+        if let Some(min_pause) = self.duration(CHECKPOINT_MIN_PAUSE)
+            && min_pause >= interval
+        {
+            self.report(
+                Severity::Warning,
+                CHECKPOINT_MIN_PAUSE,
+                format!(
+                    "min-pause {:?} is not shorter than the interval {:?}, the effective interval becomes \
+                     the checkpoint duration plus the min-pause",
+                    min_pause, interval
+                ),
+            );
+        }
+        if self.config.get(&CHECKPOINT_DIR_KEYS).is_none() {
+            self.report(
+                Severity::Error,
+                CHECKPOINT_DIR_KEYS[0],
+                String::from(
+                    "no checkpoint directory, checkpoints are kept in JobManager memory and lost on failover",
+                ),
+            );
+        }
+        Some(interval)
+    }
+
+    fn restart_strategy(&mut self, checkpointing: bool) {
+        let Some((key, value)) = self.config.get(&RESTART_STRATEGY_KEYS) else {
+            let message = if checkpointing {
+                "no restart strategy, with checkpointing enabled Flink restarts a failing job forever"
+            } else {
+                "no restart strategy, without checkpointing the job fails on its first error"
+            };
+            self.report(
+                Severity::Warning,
+                RESTART_STRATEGY_KEYS[0],
+                message.to_string(),
+            );
+            return;
+        };
+        match value.to_lowercase().as_str() {
+            "none" | "off" | "disable" => self.report(
+                Severity::Warning,
+                key,
+                String::from("restarts are disabled, the job fails on its first task failure"),
+            ),
+            "fixed-delay" | "fixeddelay" => {
+                self.duration("restart-strategy.fixed-delay.delay");
+            }
+            "failure-rate" | "failurerate" => {
+                self.duration("restart-strategy.failure-rate.delay");
+                self.duration("restart-strategy.failure-rate.failure-rate-interval");
+            }
+            "exponential-delay" | "exponentialdelay" => {
+                self.duration("restart-strategy.exponential-delay.initial-backoff");
+                self.duration("restart-strategy.exponential-delay.max-backoff");
+            }
+            _ => self.report(
+                Severity::Error,
+                key,
+                format!(
+                    "unknown restart strategy {:?}, expected none, fixed-delay, failure-rate or exponential-delay",
+                    value
+                ),
+            ),
+        }
+    }
+
+    fn state_backend(&mut self) {
+        let Some((key, value)) = self.config.get(&STATE_BACKEND_KEYS) else {
+            return;
+        };
+        let backend = value.to_lowercase();
+        if backend == "jobmanager" || backend == "filesystem" {
+            self.report(
+                Severity::Warning,
+                key,
+                format!(
+                    "{:?} is deprecated, use hashmap with a checkpoint directory",
+                    value
+                ),
+            );
+        } else if !self
+            .rules
+            .state_backends
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(&backend))
+        {
+            self.report(
+                Severity::Error,
+                key,
+                format!(
+                    "state backend {:?} is not allowed, expected one of {}",
+                    value,
+                    self.rules.state_backends.join(", ")
+                ),
+            );
+        }
+    }
+
+    fn parallelism(&mut self) {
+        let parallelism = match self.job.parallelism {
+            Some(parallelism) => parallelism,
+            None => match self.config.get(&[DEFAULT_PARALLELISM]) {
+                Some((key, value)) => match value.parse() {
+                    Ok(parallelism) => parallelism,
+                    Err(_) => {
+                        self.report(Severity::Error, key, format!("{:?} is not a number", value));
+                        return;
+                    }
+                },
+                None => 1,
+            },
+        };
+        if parallelism < 1 {
+            self.report(
+                Severity::Error,
+                "parallelism",
+                format!("parallelism {} must be at least 1", parallelism),
+            );
+            return;
+        }
+        let Some((key, value)) = self.config.get(&[MAX_PARALLELISM]) else {
+            if self.rules.require_max_parallelism {
+                self.report(
+                    Severity::Warning,
+                    MAX_PARALLELISM,
+                    String::from(
+                        "not set, Flink derives it from the first deployment and it cannot change \
+                         without discarding keyed state",
+                    ),
+                );
+            }
+            return;
+        };
+        match value.parse::<i32>() {
+            Ok(max) if !(1..=MAX_PARALLELISM_LIMIT).contains(&max) => self.report(
+                Severity::Error,
+                key,
+                format!("{} must be between 1 and {}", max, MAX_PARALLELISM_LIMIT),
+            ),
+            Ok(max) if parallelism > max => self.report(
+                Severity::Error,
+                key,
+                format!(
+                    "parallelism {} exceeds max parallelism {}, the job cannot be submitted",
+                    parallelism, max
+                ),
+            ),
+            Ok(_) => {}
+            Err(_) => self.report(Severity::Error, key, format!("{:?} is not a number", value)),
+        }
+    }
+
+    fn savepoint_dir(&mut self, manifest_dir: Option<&str>) {
+        let severity = match self.job.savepoint_policy {
+            SavepointPolicy::Required => Severity::Error,
+            SavepointPolicy::BestEffort => Severity::Warning,
+            SavepointPolicy::None => return,
+        };
+        if self.job.savepoint_dir.is_none()
+            && manifest_dir.is_none()
+            && self.config.get(&SAVEPOINT_DIR_KEYS).is_none()
+        {
+            self.report(
+                severity,
+                SAVEPOINT_DIR_KEYS[0],
+                String::from(
+                    "no savepoint directory in the manifest or the configuration, \
+                     upgrades cannot take a savepoint",
+                ),
+            );
+        }
+    }
+}
+
+/// 检查作业提交前的配置，`cluster` 为 `/jobmanager/config` 中的集群配置，
+/// `savepoint_dir` 为 manifest 中的默认 savepoint 目录
+pub fn lint_job(
+    job: &DesiredJob,
+    savepoint_dir: Option<&str>,
+    cluster: &BTreeMap<String, String>,
+    rules: &LintRules,
+) -> Vec<LintIssue> {
+    let mut linter = Linter {
+        job,
+        config: Effective {
+            job: &job.config,
+            cluster,
+        },
+        rules,
+        issues: Vec::new(),
+    };
+    let interval = linter.checkpointing();
+    linter.restart_strategy(interval.is_some());
+    linter.state_backend();
+    linter.parallelism();
+    linter.savepoint_dir(savepoint_dir);
+    linter.issues
+}
+
+pub fn lint_manifest(
+    manifest: &Manifest,
+    cluster: &BTreeMap<String, String>,
+    rules: &LintRules,
+) -> Vec<LintIssue> {
+    manifest
+        .jobs
+        .iter()
+        .flat_map(|job| lint_job(job, manifest.savepoint_dir.as_deref(), cluster, rules))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("500"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_duration("30 s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("1min"), Some(Duration::from_secs(60)));
+        assert_eq!(parse_duration("2 h"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("1 fortnight"), None);
+        assert_eq!(parse_duration("s"), None);
+    }
+
+    #[test]
+    fn test_lint_job() {
+        let job: DesiredJob = toml::from_str(
+            r#"
+            name = "orders-etl"
+            jar = "orders.jar"
+            parallelism = 8
+
+            [config]
+            "execution.checkpointing.interval" = "5 min"
+            "execution.checkpointing.timeout" = "1 min"
+            "pipeline.max-parallelism" = "4"
+            "state.backend.type" = "leveldb"
+            "#,
+        )
+        .unwrap();
+        let cluster = BTreeMap::from([
+            (
+                "state.checkpoints.dir".to_string(),
+                "s3://flink/checkpoints".to_string(),
+            ),
+            (
+                "restart-strategy.type".to_string(),
+                "fixed-delay".to_string(),
+            ),
+            (
+                "restart-strategy.fixed-delay.delay".to_string(),
+                "ten seconds".to_string(),
+            ),
+        ]);
+        let issues = lint_job(&job, None, &cluster, &LintRules::default());
+        let found: Vec<(Severity, &str)> = issues
+            .iter()
+            .map(|issue| (issue.severity, issue.key.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Severity::Warning, CHECKPOINT_TIMEOUT),
+                (Severity::Error, "restart-strategy.fixed-delay.delay"),
+                (Severity::Error, "state.backend.type"),
+                (Severity::Error, MAX_PARALLELISM),
+                (Severity::Error, "state.savepoints.dir"),
+            ]
+        );
+
+        let issues = lint_job(
+            &job,
+            Some("s3://flink/savepoints"),
+            &cluster,
+            &LintRules::default(),
+        );
+        assert!(
+            issues
+                .iter()
+                .all(|issue| issue.key != "state.savepoints.dir")
+        );
+    }
+}
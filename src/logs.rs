@@ -0,0 +1,339 @@
+use crate::client::error::FlinkError;
+use crate::client::flink::FlinkClient;
+use crate::models::cluster::{LogInfo, LogList};
+use regex::Regex;
+use std::fmt;
+use std::thread::sleep;
+use std::time::Duration;
+
+/// tail 时每次向前多读取的字节数
+const TAIL_CHUNK: u64 = 64 * 1024;
+
+/// 日志所在的进程
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogSource {
+    JobManager,
+    TaskManager(String),
+}
+
+impl LogSource {
+    /// `jobmanager` 或 TaskManager id
+    pub fn parse(value: &str) -> LogSource {
+        match value {
+            "jobmanager" | "jm" => LogSource::JobManager,
+            id => LogSource::TaskManager(id.to_string()),
+        }
+    }
+
+    fn list_path(&self) -> String {
+        match self {
+            LogSource::JobManager => String::from("/jobmanager/logs"),
+            LogSource::TaskManager(id) => format!("/taskmanagers/{}/logs", id),
+        }
+    }
+
+    fn file_path(&self, name: &str) -> String {
+        format!("{}/{}", self.list_path(), name)
+    }
+}
+
+impl fmt::Display for LogSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogSource::JobManager => f.write_str("jobmanager"),
+            LogSource::TaskManager(id) => f.write_str(id),
+        }
+    }
+}
+
+pub fn list_logs(client: &FlinkClient, source: &LogSource) -> Result<Vec<LogInfo>, FlinkError> {
+    let list: LogList = client.get_json(&source.list_path())?;
+    Ok(list.logs)
+}
+
+/// 读取日志文件的 `[start, end)` 字节；服务端不支持 Range 时下载整个文件后在本地截取
+pub fn read_log(
+    client: &FlinkClient,
+    source: &LogSource,
+    name: &str,
+    start: u64,
+    end: Option<u64>,
+) -> Result<String, FlinkError> {
+    let (bytes, _) = read_bytes(client, source, name, start, end)?;
+    // 从中间开始读取时，开头可能是被截断的多字节字符的后半部分
+    let skip = if start > 0 {
+        bytes.iter().take_while(|b| is_continuation(**b)).count()
+    } else {
+        0
+    };
+    Ok(String::from_utf8_lossy(&bytes[skip..]).into_owned())
+}
+
+/// 返回 `[start, end)` 的原始字节，以及服务端是否支持 Range
+fn read_bytes(
+    client: &FlinkClient,
+    source: &LogSource,
+    name: &str,
+    start: u64,
+    end: Option<u64>,
+) -> Result<(Vec<u8>, bool), FlinkError> {
+    let (bytes, ranged) = client.get_bytes_range(&source.file_path(name), start, end)?;
+    Ok(if ranged {
+        (bytes, true)
+    } else {
+        (slice(&bytes, start, end).to_vec(), false)
+    })
+}
+
+/// 按字节截取 `[start, end)`，超出范围的部分忽略
+fn slice(bytes: &[u8], start: u64, end: Option<u64>) -> &[u8] {
+    let start = (start as usize).min(bytes.len());
+    let end = end.map_or(bytes.len(), |end| (end as usize).clamp(start, bytes.len()));
+    &bytes[start..end]
+}
+
+/// UTF-8 多字节字符的后续字节
+fn is_continuation(byte: u8) -> bool {
+    byte & 0b1100_0000 == 0b1000_0000
+}
+
+fn log_size(client: &FlinkClient, source: &LogSource, name: &str) -> Result<u64, FlinkError> {
+    Ok(list_logs(client, source)?
+        .into_iter()
+        .find(|log| log.name == name)
+        .map(|log| log.size)
+        .unwrap_or_default())
+}
+
+/// 返回日志最后 `lines` 行，从文件末尾按块向前读取；
+/// 同时返回读到的文件大小，`-f` 从这里开始跟踪，不会漏掉两次请求之间追加的内容
+pub fn tail(
+    client: &FlinkClient,
+    source: &LogSource,
+    name: &str,
+    lines: usize,
+) -> Result<(Vec<String>, u64), FlinkError> {
+    let size = log_size(client, source, name)?;
+    let mut start = size;
+    while start > 0 {
+        start = start.saturating_sub(TAIL_CHUNK);
+        let (bytes, ranged) = read_bytes(client, source, name, start, Some(size))?;
+        // 服务端忽略 Range 时已经拿到了整个文件，直接在其中取最后几行，不再按块重复下载
+        if !ranged {
+            start = 0;
+        }
+        let mut result: Vec<String> = String::from_utf8_lossy(&bytes)
+            .lines()
+            .map(|l| l.to_string())
+            .collect();
+        // 第一行可能是被截断的半行（包括被截断的多字节字符），丢弃
+        if start > 0 && !result.is_empty() {
+            result.remove(0);
+        }
+        if result.len() >= lines || start == 0 {
+            let skip = result.len().saturating_sub(lines);
+            return Ok((result.split_off(skip), size));
+        }
+    }
+    Ok((Vec::new(), size))
+}
+
+/// 类似 `tail -f`：记录已读取的偏移量，每次轮询只拉取新追加的字节
+pub struct LogFollower<'a> {
+    client: &'a FlinkClient,
+    source: LogSource,
+    name: String,
+    offset: u64,
+    /// 还没有以换行结尾的字节，可能包含被轮询边界截断的多字节字符
+    pending: Vec<u8>,
+    warned: bool,
+}
+
+impl<'a> LogFollower<'a> {
+    /// 从字节偏移量 `offset` 开始跟踪，通常是 [`tail`] 返回的文件大小
+    pub fn new(
+        client: &'a FlinkClient,
+        source: LogSource,
+        name: &str,
+        offset: u64,
+    ) -> LogFollower<'a> {
+        LogFollower {
+            client,
+            source,
+            name: name.to_string(),
+            offset,
+            pending: Vec::new(),
+            warned: false,
+        }
+    }
+
+    /// 返回自上次轮询以来新增的完整行；文件变小（被滚动）时从头读取。
+    /// 服务端不支持 Range 时每次下载整个文件，从上次的偏移量截取，并提示一次
+    pub fn poll(&mut self) -> Result<Vec<String>, FlinkError> {
+        let size = log_size(self.client, &self.source, &self.name)?;
+        if size < self.offset {
+            self.offset = 0;
+            self.pending.clear();
+        }
+        if size == self.offset {
+            return Ok(Vec::new());
+        }
+        let (bytes, ranged) = read_bytes(
+            self.client,
+            &self.source,
+            &self.name,
+            self.offset,
+            Some(size),
+        )?;
+        if !ranged && !self.warned {
+            self.warned = true;
+            eprintln!(
+                "warning: {} ignores Range requests, downloading the whole file on every poll",
+                self.client.url(&self.source.file_path(&self.name))
+            );
+        }
+        self.offset = size;
+        self.pending.extend_from_slice(&bytes);
+        Ok(take_complete_lines(&mut self.pending))
+    }
+
+    /// 持续轮询，`on_line` 返回 false 时停止
+    pub fn follow(
+        &mut self,
+        interval: Duration,
+        filter: Option<&Regex>,
+        mut on_line: impl FnMut(&str) -> bool,
+    ) -> Result<(), FlinkError> {
+        loop {
+            for line in self.poll()? {
+                if filter.is_some_and(|re| !re.is_match(&line)) {
+                    continue;
+                }
+                if !on_line(&line) {
+                    return Ok(());
+                }
+            }
+            sleep(interval);
+        }
+    }
+}
+
+/// 取出 `buffer` 中所有以换行结尾的行，剩下的半行留在 `buffer` 里。
+/// 换行符不会出现在多字节字符内部，取出的部分总是完整的 UTF-8
+fn take_complete_lines(buffer: &mut Vec<u8>) -> Vec<String> {
+    let Some(last_newline) = buffer.iter().rposition(|b| *b == b'\n') else {
+        return Vec::new();
+    };
+    let rest = buffer.split_off(last_newline + 1);
+    let complete = std::mem::replace(buffer, rest);
+    String::from_utf8_lossy(&complete)
+        .lines()
+        .map(|l| l.to_string())
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct LogMatch {
+    pub source: LogSource,
+    pub file: String,
+    pub line_number: usize,
+    pub line: String,
+}
+
+impl fmt::Display for LogMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.source, self.file, self.line_number, self.line
+        )
+    }
+}
+
+/// 在 JobManager 和所有 TaskManager 的日志中搜索，过滤在客户端完成
+///
+/// `file` 指定只搜索某个文件名（如 `taskmanager.log`），默认搜索所有 `.log` 文件；
+/// `max_bytes` 限制每个文件只读取末尾的若干字节，此时行号从读取位置开始计算。
+pub fn grep(
+    client: &FlinkClient,
+    pattern: &Regex,
+    file: Option<&str>,
+    max_bytes: Option<u64>,
+) -> Result<Vec<LogMatch>, FlinkError> {
+    let mut sources = vec![LogSource::JobManager];
+    sources.extend(
+        client
+            .taskmanagers()?
+            .into_iter()
+            .map(|tm| LogSource::TaskManager(tm.id)),
+    );
+
+    let mut matches = Vec::new();
+    for source in sources {
+        for log in list_logs(client, &source)? {
+            let wanted = match file {
+                Some(file) => log.name == file,
+                None => log.name.ends_with(".log"),
+            };
+            if !wanted {
+                continue;
+            }
+            let start = max_bytes.map_or(0, |max| log.size.saturating_sub(max));
+            let text = read_log(client, &source, &log.name, start, None)?;
+            matches.extend(
+                grep_text(&text, pattern).map(|(line_number, line)| LogMatch {
+                    source: source.clone(),
+                    file: log.name.clone(),
+                    line_number,
+                    line: line.to_string(),
+                }),
+            );
+        }
+    }
+    Ok(matches)
+}
+
+fn grep_text<'a>(text: &'a str, pattern: &'a Regex) -> impl Iterator<Item = (usize, &'a str)> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| pattern.is_match(line))
+        .map(|(i, line)| (i + 1, line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_complete_lines() {
+        let mut buffer = b"a\nb\npart".to_vec();
+        assert_eq!(take_complete_lines(&mut buffer), vec!["a", "b"]);
+        assert_eq!(buffer, b"part");
+        buffer.extend_from_slice(b"ial\n");
+        assert_eq!(take_complete_lines(&mut buffer), vec!["partial"]);
+        assert!(buffer.is_empty());
+        assert!(take_complete_lines(&mut buffer).is_empty());
+
+        // 多字节字符被轮询边界截断时留到下一次
+        let text = "检查点\n".as_bytes();
+        let mut buffer = text[..4].to_vec();
+        assert!(take_complete_lines(&mut buffer).is_empty());
+        buffer.extend_from_slice(&text[4..]);
+        assert_eq!(take_complete_lines(&mut buffer), vec!["检查点"]);
+    }
+
+    #[test]
+    fn test_slice() {
+        assert_eq!(slice(b"line 1\nline 2\n", 7, Some(13)), b"line 2");
+        assert_eq!(slice(b"short", 3, None), b"rt");
+        assert_eq!(slice(b"short", 10, Some(20)), b"");
+    }
+
+    #[test]
+    fn test_grep_text() {
+        let text = "INFO started\nERROR boom\nWARN slow\nERROR again";
+        let pattern = Regex::new("^ERROR").unwrap();
+        let found: Vec<(usize, &str)> = grep_text(text, &pattern).collect();
+        assert_eq!(found, vec![(2, "ERROR boom"), (4, "ERROR again")]);
+    }
+}
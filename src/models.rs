@@ -1,3 +1,7 @@
+pub mod cluster;
 pub mod enums;
 pub mod flink;
+pub mod operator;
+pub mod plan;
+pub mod sql_gateway;
 pub mod structs;
@@ -0,0 +1,86 @@
+use serde::Deserialize;
+
+/// `/overview` 的响应
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ClusterOverview {
+    #[serde(default)]
+    pub taskmanagers: i32,
+    #[serde(rename = "slots-total", default)]
+    pub slots_total: i32,
+    #[serde(rename = "slots-available", default)]
+    pub slots_available: i32,
+    #[serde(rename = "jobs-running", default)]
+    pub jobs_running: i32,
+    #[serde(rename = "flink-version", default)]
+    pub flink_version: String,
+}
+
+/// `/taskmanagers` 的响应
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct TaskManagerList {
+    #[serde(default)]
+    pub taskmanagers: Vec<TaskManagerInfo>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TaskManagerInfo {
+    pub id: String,
+    #[serde(default)]
+    pub path: String,
+    #[serde(rename = "slotsNumber", default)]
+    pub slots_number: i32,
+    #[serde(rename = "freeSlots", default)]
+    pub free_slots: i32,
+    #[serde(rename = "timeSinceLastHeartbeat", default)]
+    pub time_since_last_heartbeat: i64,
+    #[serde(default)]
+    pub hardware: Option<Hardware>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Hardware {
+    #[serde(default)]
+    pub cpu_cores: i32,
+    #[serde(default)]
+    pub physical_memory: i64,
+    #[serde(default)]
+    pub free_memory: i64,
+    #[serde(default)]
+    pub managed_memory: i64,
+}
+
+/// `/jobs/:jid/vertices/:vid/taskmanagers` 的响应
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct VertexTaskManagers {
+    #[serde(default)]
+    pub taskmanagers: Vec<VertexTaskManager>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct VertexTaskManager {
+    #[serde(default)]
+    pub host: String,
+    #[serde(default)]
+    pub status: String,
+    /// Flink 1.13 之前没有这个字段
+    #[serde(rename = "taskmanager-id", default)]
+    pub taskmanager_id: Option<String>,
+}
+
+/// `/jobmanager/logs` 与 `/taskmanagers/:id/logs` 的响应
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct LogList {
+    #[serde(default)]
+    pub logs: Vec<LogInfo>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct LogInfo {
+    pub name: String,
+    #[serde(default)]
+    pub size: u64,
+    /// 最后修改时间，Flink 1.15 之前没有这个字段
+    #[serde(default)]
+    pub mtime: Option<i64>,
+}
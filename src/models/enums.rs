@@ -1,4 +1,78 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
 pub enum YesNo {
     YES,
     NO,
 }
+
+/// Flink 作业状态，对应 REST 接口返回的 `state` 字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum JobState {
+    Initializing,
+    Created,
+    Running,
+    Failing,
+    Failed,
+    Cancelling,
+    Canceled,
+    Finished,
+    Restarting,
+    Suspended,
+    Reconciling,
+    #[serde(other)]
+    Unknown,
+}
+
+impl JobState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobState::Initializing => "INITIALIZING",
+            JobState::Created => "CREATED",
+            JobState::Running => "RUNNING",
+            JobState::Failing => "FAILING",
+            JobState::Failed => "FAILED",
+            JobState::Cancelling => "CANCELLING",
+            JobState::Canceled => "CANCELED",
+            JobState::Finished => "FINISHED",
+            JobState::Restarting => "RESTARTING",
+            JobState::Suspended => "SUSPENDED",
+            JobState::Reconciling => "RECONCILING",
+            JobState::Unknown => "UNKNOWN",
+        }
+    }
+
+    /// 作业已经结束（不会再变化）
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            JobState::Failed | JobState::Canceled | JobState::Finished
+        )
+    }
+}
+
+impl From<&str> for JobState {
+    fn from(value: &str) -> Self {
+        match value.to_ascii_uppercase().as_str() {
+            "INITIALIZING" => JobState::Initializing,
+            "CREATED" => JobState::Created,
+            "RUNNING" => JobState::Running,
+            "FAILING" => JobState::Failing,
+            "FAILED" => JobState::Failed,
+            "CANCELLING" => JobState::Cancelling,
+            "CANCELED" => JobState::Canceled,
+            "FINISHED" => JobState::Finished,
+            "RESTARTING" => JobState::Restarting,
+            "SUSPENDED" => JobState::Suspended,
+            "RECONCILING" => JobState::Reconciling,
+            _ => JobState::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for JobState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -1,5 +1,8 @@
+use crate::models::enums::JobState;
+use crate::naming::JobMetadata;
 use serde::de::{self, Visitor};
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
 use std::fmt;
 
 /// 包装类型：可以将字符串或数字转换为 i64
@@ -101,19 +104,26 @@ impl<'de> Deserialize<'de> for StringOrNumber {
     }
 }
 
-#[derive(Debug)]
+/// 序列化时使用与 REST 接口相同的字段名，保证可以再反序列化回来
+#[derive(Debug, Clone, Serialize)]
 pub struct Flink {
     pub jid: String,
     pub name: String,
     pub state: String,
+    #[serde(rename = "start-time")]
     pub start_time: Option<i64>,
+    #[serde(rename = "end-time")]
     pub end_time: Option<String>,
     pub duration: Option<String>,
+    #[serde(rename = "last-modification")]
     pub last_modification: Option<String>,
     pub tasks: TaskInfo,
+    /// 按命名规则从作业名称解析出的字段，读取作业列表后由 [`crate::naming::NamingConvention::annotate`] 填充
+    #[serde(skip)]
+    pub metadata: JobMetadata,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
 pub struct TaskInfo {
     pub total: i32,
     pub created: i32,
@@ -128,6 +138,39 @@ pub struct TaskInfo {
     pub initializing: i32,
 }
 
+impl Flink {
+    pub fn job_state(&self) -> JobState {
+        JobState::from(self.state.as_str())
+    }
+
+    /// 同名作业（例如历史上被取消的旧版本）之间的优先级：未结束的优先，其次是最新启动的
+    pub fn preference(&self) -> (bool, Option<i64>) {
+        (!self.job_state().is_terminal(), self.start_time)
+    }
+
+    /// 按作业名称索引，同名作业按 [`Flink::preference`] 取一个；
+    /// `T` 可以是 `Flink` 本身或快照等包含作业的类型
+    pub fn latest_by_name<T: AsRef<Flink>>(jobs: &[T]) -> BTreeMap<&str, &T> {
+        let mut result: BTreeMap<&str, &T> = BTreeMap::new();
+        for item in jobs {
+            let job = item.as_ref();
+            match result.get(job.name.as_str()) {
+                Some(existing) if existing.as_ref().preference() >= job.preference() => {}
+                _ => {
+                    result.insert(job.name.as_str(), item);
+                }
+            }
+        }
+        result
+    }
+}
+
+impl AsRef<Flink> for Flink {
+    fn as_ref(&self) -> &Flink {
+        self
+    }
+}
+
 impl<'de> Deserialize<'de> for Flink {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -242,6 +285,7 @@ impl<'de> Deserialize<'de> for Flink {
                     duration,
                     last_modification,
                     tasks,
+                    metadata: JobMetadata::default(),
                 })
             }
         }
@@ -259,3 +303,293 @@ impl<'de> Deserialize<'de> for Flink {
         deserializer.deserialize_struct("Flink", FIELDS, FlinkVisitor)
     }
 }
+
+/// `/jobs/overview` 的响应
+#[derive(Deserialize, Debug)]
+pub struct JobsOverview {
+    pub jobs: Vec<Flink>,
+}
+
+/// `/jobs/:jid` 的响应
+#[derive(Deserialize, Debug, Clone)]
+pub struct JobDetail {
+    pub jid: String,
+    pub name: String,
+    pub state: String,
+    #[serde(rename = "start-time", default)]
+    pub start_time: i64,
+    #[serde(rename = "end-time", default)]
+    pub end_time: i64,
+    #[serde(default)]
+    pub duration: i64,
+    #[serde(default)]
+    pub vertices: Vec<JobVertex>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct JobVertex {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub parallelism: i32,
+    #[serde(rename = "maxParallelism", default)]
+    pub max_parallelism: i32,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub duration: i64,
+    /// 各状态的 subtask 数量，如 `RUNNING -> 2`
+    #[serde(default)]
+    pub tasks: BTreeMap<String, i32>,
+}
+
+/// `/jobs/:jid/checkpoints` 的响应
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct CheckpointStats {
+    #[serde(default)]
+    pub counts: CheckpointCounts,
+    #[serde(default)]
+    pub latest: LatestCheckpoints,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct CheckpointCounts {
+    #[serde(default)]
+    pub restored: i64,
+    #[serde(default)]
+    pub total: i64,
+    #[serde(default)]
+    pub in_progress: i64,
+    #[serde(default)]
+    pub completed: i64,
+    #[serde(default)]
+    pub failed: i64,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct LatestCheckpoints {
+    pub completed: Option<CheckpointInfo>,
+    pub savepoint: Option<CheckpointInfo>,
+    pub failed: Option<CheckpointInfo>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CheckpointInfo {
+    pub id: i64,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub is_savepoint: bool,
+    #[serde(default)]
+    pub trigger_timestamp: i64,
+    #[serde(default)]
+    pub latest_ack_timestamp: i64,
+    #[serde(default)]
+    pub state_size: i64,
+    #[serde(default)]
+    pub checkpointed_size: i64,
+    #[serde(default)]
+    pub end_to_end_duration: i64,
+    #[serde(default)]
+    pub alignment_buffered: i64,
+    pub external_path: Option<String>,
+    pub failure_message: Option<String>,
+}
+
+/// `/jobs/:jid/checkpoints/config` 的响应，时间单位为毫秒
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct CheckpointConfigInfo {
+    #[serde(default)]
+    pub mode: String,
+    #[serde(default)]
+    pub interval: i64,
+    #[serde(default)]
+    pub timeout: i64,
+}
+
+/// `/jobs/:jid/checkpoints/details/:cid` 的响应，这里只需要参与的顶点 id
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct CheckpointDetail {
+    pub id: i64,
+    #[serde(default)]
+    pub tasks: BTreeMap<String, de::IgnoredAny>,
+}
+
+/// `/jobs/:jid/checkpoints/details/:cid/subtasks/:vid` 的响应中的汇总部分
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct SubtaskCheckpointStats {
+    #[serde(default)]
+    pub summary: SubtaskCheckpointSummary,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct SubtaskCheckpointSummary {
+    #[serde(default)]
+    pub alignment: AlignmentSummary,
+    #[serde(default)]
+    pub start_delay: MinMaxAvg,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct AlignmentSummary {
+    #[serde(default)]
+    pub duration: MinMaxAvg,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct MinMaxAvg {
+    #[serde(default)]
+    pub min: i64,
+    #[serde(default)]
+    pub max: i64,
+    #[serde(default)]
+    pub avg: i64,
+}
+
+/// `/jobs/:jid/exceptions` 的响应，兼容新旧两种格式
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct JobExceptions {
+    #[serde(rename = "root-exception")]
+    pub root_exception: Option<String>,
+    pub timestamp: Option<i64>,
+    #[serde(rename = "exceptionHistory", default)]
+    pub exception_history: ExceptionHistory,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ExceptionHistory {
+    #[serde(default)]
+    pub entries: Vec<ExceptionEntry>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ExceptionEntry {
+    #[serde(rename = "exceptionName", default)]
+    pub exception_name: String,
+    #[serde(default)]
+    pub stacktrace: String,
+    #[serde(default)]
+    pub timestamp: i64,
+    #[serde(rename = "taskName")]
+    pub task_name: Option<String>,
+    pub location: Option<String>,
+}
+
+impl JobExceptions {
+    /// 最近一次异常：优先取 exceptionHistory，没有时退回 root-exception
+    pub fn latest(&self) -> Option<ExceptionEntry> {
+        if let Some(entry) = self.exception_history.entries.first() {
+            return Some(entry.clone());
+        }
+        self.root_exception
+            .as_ref()
+            .map(|stacktrace| ExceptionEntry {
+                exception_name: stacktrace.lines().next().unwrap_or_default().to_string(),
+                stacktrace: stacktrace.clone(),
+                timestamp: self.timestamp.unwrap_or_default(),
+                task_name: None,
+                location: None,
+            })
+    }
+}
+
+/// `/jobs/:jid/config` 的响应
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct JobConfig {
+    pub jid: String,
+    pub name: String,
+    #[serde(rename = "execution-config", default)]
+    pub execution_config: Option<ExecutionConfig>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct ExecutionConfig {
+    #[serde(rename = "execution-mode", default)]
+    pub execution_mode: String,
+    #[serde(rename = "restart-strategy", default)]
+    pub restart_strategy: String,
+    #[serde(rename = "job-parallelism", default)]
+    pub job_parallelism: i32,
+    #[serde(rename = "object-reuse-mode", default)]
+    pub object_reuse_mode: bool,
+    #[serde(rename = "user-config", default)]
+    pub user_config: BTreeMap<String, String>,
+}
+
+/// `/jobs/:jid/jobmanager/config` 以及 `/jobmanager/config` 的条目
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ConfigEntry {
+    pub key: String,
+    pub value: String,
+}
+
+/// `/jars` 的响应
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct JarList {
+    #[serde(default)]
+    pub files: Vec<JarFile>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct JarFile {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub uploaded: i64,
+}
+
+/// `/jars/upload` 的响应，`filename` 为服务器上的完整路径，最后一段即 jar id
+#[derive(Deserialize, Debug, Clone)]
+pub struct JarUploadResponse {
+    pub filename: String,
+    pub status: String,
+}
+
+/// `/jars/:jarid/run` 的请求体
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct JarRunRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry_class: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub program_args_list: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallelism: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub savepoint_path: Option<String>,
+    pub allow_non_restored_state: bool,
+    /// 作业级别的 Flink 配置（Flink 1.17+）
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub flink_configuration: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct JarRunResponse {
+    pub jobid: String,
+}
+
+/// 异步操作（savepoint、stop 等）触发后返回的 id
+#[derive(Deserialize, Debug, Clone)]
+pub struct TriggerResponse {
+    #[serde(rename = "request-id")]
+    pub request_id: String,
+}
+
+/// 异步操作的状态，`status.id` 为 `IN_PROGRESS` 或 `COMPLETED`
+#[derive(Deserialize, Debug, Clone)]
+pub struct AsynchronousOperation {
+    pub status: QueueStatus,
+    pub operation: Option<SavepointOperation>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct QueueStatus {
+    pub id: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SavepointOperation {
+    pub location: Option<String>,
+    #[serde(rename = "failure-cause")]
+    pub failure_cause: Option<serde_json::Value>,
+}
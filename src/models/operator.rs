@@ -0,0 +1,237 @@
+use crate::models::enums::JobState;
+use crate::models::flink::{Flink, TaskInfo};
+use crate::naming::JobMetadata;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Flink Kubernetes Operator 的 CRD 版本
+pub const API_VERSION: &str = "flink.apache.org/v1beta1";
+
+/// 按 `kind` 区分的 operator 资源
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "kind")]
+pub enum OperatorResource {
+    FlinkDeployment(FlinkDeployment),
+    FlinkSessionJob(FlinkSessionJob),
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct ObjectMeta {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>,
+}
+
+/// Application 或 Session 集群，带有 `job` 时为 Application 模式
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FlinkDeployment {
+    pub api_version: String,
+    pub metadata: ObjectMeta,
+    pub spec: FlinkDeploymentSpec,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<ResourceStatus>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct FlinkDeploymentSpec {
+    #[serde(default)]
+    pub image: String,
+    /// 例如 `v1_17`
+    #[serde(default)]
+    pub flink_version: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub flink_configuration: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_account: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    #[serde(default)]
+    pub job_manager: ComponentSpec,
+    #[serde(default)]
+    pub task_manager: ComponentSpec,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job: Option<JobSpec>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct ComponentSpec {
+    #[serde(default)]
+    pub resource: ResourceSpec,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replicas: Option<i32>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct ResourceSpec {
+    /// Kubernetes 数量格式，例如 `2048m`、`2Gi`
+    #[serde(default)]
+    pub memory: String,
+    #[serde(default)]
+    pub cpu: f64,
+}
+
+/// 提交到已有 Session 集群（`deploymentName`）中的作业
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FlinkSessionJob {
+    pub api_version: String,
+    pub metadata: ObjectMeta,
+    pub spec: FlinkSessionJobSpec,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<ResourceStatus>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct FlinkSessionJobSpec {
+    #[serde(default)]
+    pub deployment_name: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub flink_configuration: BTreeMap<String, String>,
+    pub job: JobSpec,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct JobSpec {
+    #[serde(rename = "jarURI", default)]
+    pub jar_uri: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_class: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parallelism: Option<i32>,
+    #[serde(default)]
+    pub upgrade_mode: UpgradeMode,
+    #[serde(default)]
+    pub state: DesiredState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_savepoint_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_non_restored_state: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum UpgradeMode {
+    #[default]
+    Stateless,
+    Savepoint,
+    LastState,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DesiredState {
+    #[default]
+    Running,
+    Suspended,
+}
+
+/// operator 写回的 `status`，FlinkDeployment 和 FlinkSessionJob 共用
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceStatus {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job_status: Option<OperatorJobStatus>,
+    /// `CREATED`、`DEPLOYED`、`STABLE`、`UPGRADING`、`SUSPENDED`、`ROLLED_BACK`、`FAILED` 等
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lifecycle_state: Option<String>,
+    /// 仅 FlinkDeployment：`READY`、`DEPLOYING`、`MISSING`、`ERROR` 等
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job_manager_deployment_status: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 时间戳在 CR 中以字符串形式保存的毫秒数
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct OperatorJobStatus {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update_time: Option<String>,
+}
+
+impl OperatorResource {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            OperatorResource::FlinkDeployment(_) => "FlinkDeployment",
+            OperatorResource::FlinkSessionJob(_) => "FlinkSessionJob",
+        }
+    }
+
+    pub fn api_version(&self) -> &str {
+        match self {
+            OperatorResource::FlinkDeployment(d) => &d.api_version,
+            OperatorResource::FlinkSessionJob(j) => &j.api_version,
+        }
+    }
+
+    pub fn metadata(&self) -> &ObjectMeta {
+        match self {
+            OperatorResource::FlinkDeployment(d) => &d.metadata,
+            OperatorResource::FlinkSessionJob(j) => &j.metadata,
+        }
+    }
+
+    /// Session 模式的 FlinkDeployment 没有作业
+    pub fn job(&self) -> Option<&JobSpec> {
+        match self {
+            OperatorResource::FlinkDeployment(d) => d.spec.job.as_ref(),
+            OperatorResource::FlinkSessionJob(j) => Some(&j.spec.job),
+        }
+    }
+
+    pub fn status(&self) -> Option<&ResourceStatus> {
+        match self {
+            OperatorResource::FlinkDeployment(d) => d.status.as_ref(),
+            OperatorResource::FlinkSessionJob(j) => j.status.as_ref(),
+        }
+    }
+
+    /// 把 `status` 转换为与 REST 接口相同的作业模型，没有作业或尚无 `status` 时返回 `None`
+    ///
+    /// operator 还未拿到作业状态时根据 `lifecycleState` 推断：`SUSPENDED`、`FAILED` 原样使用，
+    /// 其它情况视为 `CREATED`。CR 中没有任务计数，`tasks` 全部为 0。
+    pub fn to_flink(&self) -> Option<Flink> {
+        self.job()?;
+        let status = self.status()?;
+        let job_status = status.job_status.clone().unwrap_or_default();
+        let state = job_status.state.clone().unwrap_or_else(|| {
+            match status.lifecycle_state.as_deref() {
+                Some("SUSPENDED") => JobState::Suspended,
+                Some("FAILED") => JobState::Failed,
+                _ => JobState::Created,
+            }
+            .to_string()
+        });
+        Some(Flink {
+            jid: job_status.job_id.unwrap_or_default(),
+            name: job_status
+                .job_name
+                .unwrap_or_else(|| self.metadata().name.clone()),
+            state,
+            start_time: job_status.start_time.and_then(|t| t.parse().ok()),
+            end_time: None,
+            duration: None,
+            last_modification: job_status.update_time,
+            tasks: TaskInfo::default(),
+            metadata: JobMetadata::default(),
+        })
+    }
+}
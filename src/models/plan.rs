@@ -0,0 +1,51 @@
+use serde::Deserialize;
+
+/// `/jobs/:jid/plan` 的响应
+#[derive(Deserialize, Debug, Clone)]
+pub struct JobPlanResponse {
+    pub plan: JobPlan,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct JobPlan {
+    pub jid: String,
+    pub name: String,
+    #[serde(default)]
+    pub nodes: Vec<PlanNode>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PlanNode {
+    pub id: String,
+    #[serde(default)]
+    pub parallelism: i32,
+    #[serde(default)]
+    pub operator: String,
+    #[serde(default)]
+    pub description: String,
+    /// 上游节点，边的方向为 input.id -> 当前节点
+    #[serde(default)]
+    pub inputs: Vec<PlanInput>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PlanInput {
+    pub id: String,
+    #[serde(default)]
+    pub num: i32,
+    #[serde(default)]
+    pub ship_strategy: String,
+    #[serde(default)]
+    pub exchange: String,
+}
+
+/// `/jobs/:jid/vertices/:vid/backpressure` 的响应
+#[derive(Deserialize, Debug, Clone)]
+pub struct BackPressureInfo {
+    /// `ok` 表示已有采样结果，`deprecated` 表示采样尚未完成
+    #[serde(default)]
+    pub status: String,
+    /// `ok`、`low` 或 `high`
+    #[serde(rename = "backpressureLevel", alias = "backpressure-level")]
+    pub backpressure_level: Option<String>,
+}
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// `POST /sessions` 的响应
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenSessionResponse {
+    pub session_handle: String,
+}
+
+/// `GET /sessions/:handle` 的响应
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct SessionConfig {
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecuteStatementResponse {
+    pub operation_handle: String,
+}
+
+/// 操作状态: `INITIALIZED`、`PENDING`、`RUNNING`、`FINISHED`、`CANCELED`、`CLOSED`、`ERROR`、`TIMEOUT`
+#[derive(Deserialize, Debug, Clone)]
+pub struct OperationStatus {
+    pub status: String,
+}
+
+/// `GET /sessions/:sh/operations/:oh/result/:token` 的响应
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchResultsResponse {
+    pub result_type: ResultType,
+    #[serde(default)]
+    pub is_query_result: bool,
+    #[serde(rename = "jobID")]
+    pub job_id: Option<String>,
+    pub result_kind: Option<String>,
+    #[serde(default)]
+    pub results: ResultData,
+    pub next_result_uri: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ResultType {
+    NotReady,
+    Payload,
+    Eos,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ResultData {
+    #[serde(default)]
+    pub columns: Vec<ColumnInfo>,
+    #[serde(default)]
+    pub data: Vec<RowData>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnInfo {
+    pub name: String,
+    #[serde(default)]
+    pub logical_type: Value,
+    pub comment: Option<String>,
+}
+
+/// 流式查询的结果是 changelog，每行带有变更类型
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RowKind {
+    Insert,
+    UpdateBefore,
+    UpdateAfter,
+    Delete,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RowData {
+    pub kind: RowKind,
+    #[serde(default)]
+    pub fields: Vec<Value>,
+}
+
+impl RowData {
+    /// 按列名组装成 JSON 对象，便于反序列化为用户自定义的结构体
+    pub fn to_object(&self, columns: &[ColumnInfo]) -> Value {
+        let map: Map<String, Value> = columns
+            .iter()
+            .zip(&self.fields)
+            .map(|(column, value)| (column.name.clone(), value.clone()))
+            .collect();
+        Value::Object(map)
+    }
+}
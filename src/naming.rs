@@ -0,0 +1,203 @@
+use crate::models::flink::Flink;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// 作业命名规则，对应 config.toml 中的 `[flink.naming]`
+///
+/// 每个规则是带命名捕获组的正则表达式，例如
+/// `^team-(?P<team>[a-z0-9]+)-(?P<env>[a-z]+)-v(?P<version>\d+)$`，
+/// 捕获组的名称即元数据字段名。按顺序使用第一个匹配的规则。
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct NamingConfig {
+    #[serde(default)]
+    pub patterns: Vec<String>,
+}
+
+/// 分组时没有对应字段的作业归入该组
+pub const UNKNOWN_GROUP: &str = "(none)";
+
+#[derive(Debug, Clone, Default)]
+pub struct NamingConvention {
+    patterns: Vec<Regex>,
+}
+
+impl NamingConvention {
+    pub fn new(config: &NamingConfig) -> Result<NamingConvention, regex::Error> {
+        let patterns = config
+            .patterns
+            .iter()
+            .map(|p| Regex::new(p))
+            .collect::<Result<Vec<Regex>, _>>()?;
+        Ok(NamingConvention { patterns })
+    }
+
+    /// 解析作业名称，不符合任何规则时返回空的元数据
+    pub fn parse(&self, name: &str) -> JobMetadata {
+        let mut fields = BTreeMap::new();
+        if let Some((pattern, captures)) = self
+            .patterns
+            .iter()
+            .find_map(|p| p.captures(name).map(|c| (p, c)))
+        {
+            for field in pattern.capture_names().flatten() {
+                if let Some(value) = captures.name(field) {
+                    fields.insert(field.to_string(), value.as_str().to_string());
+                }
+            }
+        }
+        JobMetadata { fields }
+    }
+
+    /// 读取作业列表后调用一次，把解析结果保存在每个作业的 [`Flink::metadata`] 中，
+    /// 之后过滤和分组不必再执行正则
+    pub fn annotate<'a>(&self, jobs: impl IntoIterator<Item = &'a mut Flink>) {
+        for job in jobs {
+            job.metadata = self.parse(&job.name);
+        }
+    }
+
+    /// 按 `field` 分组时作业所在的组
+    pub fn group(&self, name: &str, field: &str) -> String {
+        self.parse(name)
+            .get(field)
+            .unwrap_or(UNKNOWN_GROUP)
+            .to_string()
+    }
+}
+
+/// 从作业名称中解析出的字段，例如 team、env、version
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JobMetadata {
+    pub fields: BTreeMap<String, String>,
+}
+
+impl JobMetadata {
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.fields.get(field).map(|v| v.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+impl fmt::Display for JobMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields: Vec<String> = self
+            .fields
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect();
+        f.write_str(&fields.join(" "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Term {
+    Equals(String, String),
+    NotEquals(String, String),
+    Contains(String),
+}
+
+/// 以空格分隔的过滤条件，所有条件都满足才算匹配（忽略大小写）
+///
+/// - `team=orders`：元数据字段等于某个值
+/// - `env!=prod`：元数据字段不等于某个值（没有该字段也算不等于）
+/// - 其它：作业名称包含该字符串
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JobFilter {
+    terms: Vec<Term>,
+}
+
+impl JobFilter {
+    pub fn parse(expression: &str) -> JobFilter {
+        let terms = expression
+            .split_whitespace()
+            .map(|term| {
+                if let Some((field, value)) = term.split_once("!=") {
+                    Term::NotEquals(field.to_string(), value.to_lowercase())
+                } else if let Some((field, value)) = term.split_once('=') {
+                    Term::Equals(field.to_string(), value.to_lowercase())
+                } else {
+                    Term::Contains(term.to_lowercase())
+                }
+            })
+            .collect();
+        JobFilter { terms }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// 按作业上已解析的元数据匹配，见 [`NamingConvention::annotate`]
+    pub fn matches_job(&self, job: &Flink) -> bool {
+        self.matches(&job.name, &job.metadata)
+    }
+
+    /// `metadata` 为 `name` 按命名规则解析出的字段
+    pub fn matches(&self, name: &str, metadata: &JobMetadata) -> bool {
+        let field = |field: &str| metadata.get(field).map(|v| v.to_lowercase());
+        self.terms.iter().all(|term| match term {
+            Term::Equals(f, value) => field(f).as_ref() == Some(value),
+            Term::NotEquals(f, value) => field(f).as_ref() != Some(value),
+            Term::Contains(text) => name.to_lowercase().contains(text),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convention() -> NamingConvention {
+        NamingConvention::new(&NamingConfig {
+            patterns: vec![
+                r"^team-(?P<team>[a-z0-9]+)-(?P<env>[a-z]+)-v(?P<version>\d+)$".to_string(),
+                r"^(?P<team>[a-z0-9]+)_(?P<app>.+)$".to_string(),
+            ],
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let convention = convention();
+        let metadata = convention.parse("team-orders-prod-v12");
+        assert_eq!(metadata.get("team"), Some("orders"));
+        assert_eq!(metadata.get("env"), Some("prod"));
+        assert_eq!(metadata.get("version"), Some("12"));
+        assert_eq!(metadata.to_string(), "env=prod team=orders version=12");
+
+        assert_eq!(
+            convention.parse("search_indexer").get("app"),
+            Some("indexer")
+        );
+        assert!(convention.parse("Legacy Job").is_empty());
+        assert_eq!(convention.group("Legacy Job", "team"), UNKNOWN_GROUP);
+
+        let mut jobs: Vec<Flink> = vec![serde_json::from_str(
+            r#"{"jid":"j1","name":"search_indexer","state":"RUNNING","tasks":{"total":0,"created":0,
+                "scheduled":0,"deploying":0,"running":0,"finished":0,"canceling":0,"canceled":0,
+                "failed":0,"reconciling":0,"initializing":0}}"#,
+        )
+        .unwrap()];
+        convention.annotate(&mut jobs);
+        assert_eq!(jobs[0].metadata.get("team"), Some("search"));
+        assert!(JobFilter::parse("app=indexer").matches_job(&jobs[0]));
+    }
+
+    #[test]
+    fn test_filter() {
+        let convention = convention();
+        let filter = JobFilter::parse("team=Orders env!=test v1");
+        let matches = |name: &str| filter.matches(name, &convention.parse(name));
+        assert!(matches("team-orders-prod-v12"));
+        assert!(!matches("team-orders-test-v12"));
+        assert!(!matches("team-payments-prod-v12"));
+        assert!(!matches("team-orders-prod-v2"));
+        assert!(JobFilter::parse("").matches("anything", &JobMetadata::default()));
+    }
+}
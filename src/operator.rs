@@ -0,0 +1,416 @@
+use crate::models::flink::Flink;
+use crate::models::operator::{
+    API_VERSION, ComponentSpec, FlinkDeployment, FlinkDeploymentSpec, JobSpec, ObjectMeta,
+    OperatorResource, ResourceSpec, UpgradeMode,
+};
+use crate::reconcile::{DesiredJob, SavepointPolicy};
+use regex::Regex;
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+use thiserror::Error;
+
+/// 新旧版本 Flink 中 savepoint 目录的配置项
+pub(crate) const SAVEPOINT_DIR_KEYS: [&str; 2] = [
+    "state.savepoints.dir",
+    "execution.checkpointing.savepoint-dir",
+];
+const HA_KEYS: [&str; 2] = ["high-availability", "high-availability.type"];
+
+/// 生成的 FlinkDeployment 中用户 jar 所在的目录
+const USRLIB: &str = "local:///opt/flink/usrlib";
+
+/// Kubernetes 资源数量，例如 `2048m`、`2Gi`
+static QUANTITY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\d+(\.\d+)?(Ki|Mi|Gi|Ti|Pi|Ei|k|m|M|G|T|P|E)?$").expect("valid regex")
+});
+/// RFC 1123 label
+static DNS_LABEL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[a-z0-9]([-a-z0-9]*[a-z0-9])?$").expect("valid regex"));
+/// Operator 使用的 Flink 版本写法，例如 `v1_17`
+static FLINK_VERSION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^v\d+_\d+$").expect("valid regex"));
+
+#[derive(Debug, Error)]
+pub enum OperatorError {
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("invalid manifest {path} (document {document}): {source}")]
+    Yaml {
+        path: String,
+        document: usize,
+        #[source]
+        source: serde_yaml::Error,
+    },
+}
+
+/// 解析可能包含多个文档的 YAML，忽略其它 kind 的资源（ConfigMap 等）
+///
+/// 也支持 `kubectl get flinkdeployment -o yaml` 输出的 `kind: List`。
+pub fn parse_manifests(text: &str, path: &str) -> Result<Vec<OperatorResource>, OperatorError> {
+    let mut resources = Vec::new();
+    for (i, document) in serde_yaml::Deserializer::from_str(text).enumerate() {
+        let yaml_error = |source| OperatorError::Yaml {
+            path: path.to_string(),
+            document: i + 1,
+            source,
+        };
+        let value = serde_yaml::Value::deserialize(document).map_err(yaml_error)?;
+        let items = match kind(&value) {
+            Some("List") => value
+                .get("items")
+                .and_then(|items| items.as_sequence())
+                .cloned()
+                .unwrap_or_default(),
+            _ => vec![value],
+        };
+        for item in items {
+            if matches!(kind(&item), Some("FlinkDeployment" | "FlinkSessionJob")) {
+                resources.push(serde_yaml::from_value(item).map_err(yaml_error)?);
+            }
+        }
+    }
+    Ok(resources)
+}
+
+fn kind(value: &serde_yaml::Value) -> Option<&str> {
+    value.get("kind").and_then(|kind| kind.as_str())
+}
+
+pub fn load_manifests(path: &str) -> Result<Vec<OperatorResource>, OperatorError> {
+    let text = fs::read_to_string(path).map_err(|source| OperatorError::Io {
+        path: path.to_string(),
+        source,
+    })?;
+    parse_manifests(&text, path)
+}
+
+/// 生成以 `---` 分隔的多文档 YAML
+pub fn to_yaml(resources: &[OperatorResource]) -> Result<String, serde_yaml::Error> {
+    let documents = resources
+        .iter()
+        .map(serde_yaml::to_string)
+        .collect::<Result<Vec<String>, _>>()?;
+    Ok(documents.join("---\n"))
+}
+
+/// 从 manifest 状态中取出作业，供与 REST 接口相同的监控逻辑使用
+pub fn jobs(resources: &[OperatorResource]) -> Vec<Flink> {
+    resources.iter().filter_map(|r| r.to_flink()).collect()
+}
+
+/// 校验发现的问题，`field` 为 YAML 中的路径
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub resource: String,
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.resource, self.field, self.message)
+    }
+}
+
+struct Validator {
+    resource: String,
+    issues: Vec<Issue>,
+}
+
+impl Validator {
+    fn check(&mut self, ok: bool, field: &str, message: &str) {
+        if !ok {
+            self.issues.push(Issue {
+                resource: self.resource.clone(),
+                field: field.to_string(),
+                message: message.to_string(),
+            });
+        }
+    }
+
+    fn component(&mut self, component: &ComponentSpec, field: &str) {
+        self.check(
+            QUANTITY.is_match(&component.resource.memory),
+            &format!("{}.resource.memory", field),
+            "must be a Kubernetes quantity such as 2048m or 2Gi",
+        );
+        self.check(
+            component.resource.cpu > 0.0,
+            &format!("{}.resource.cpu", field),
+            "must be greater than 0",
+        );
+        self.check(
+            component.replicas.is_none_or(|r| r > 0),
+            &format!("{}.replicas", field),
+            "must be greater than 0",
+        );
+    }
+
+    fn job(&mut self, job: &JobSpec) {
+        self.check(!job.jar_uri.is_empty(), "spec.job.jarURI", "is required");
+        self.check(
+            job.parallelism.is_none_or(|p| p > 0),
+            "spec.job.parallelism",
+            "must be greater than 0",
+        );
+    }
+}
+
+/// 在提交给 Kubernetes 之前做基本校验，返回所有发现的问题
+pub fn validate(resource: &OperatorResource) -> Vec<Issue> {
+    let name = &resource.metadata().name;
+    let mut v = Validator {
+        resource: format!("{}/{}", resource.kind(), name),
+        issues: Vec::new(),
+    };
+    v.check(
+        resource.api_version() == API_VERSION,
+        "apiVersion",
+        &format!("must be {}", API_VERSION),
+    );
+    v.check(
+        name.len() <= 63 && DNS_LABEL.is_match(name),
+        "metadata.name",
+        "must be a lowercase RFC 1123 label of at most 63 characters",
+    );
+    if let Some(job) = resource.job() {
+        v.job(job);
+    }
+
+    match resource {
+        OperatorResource::FlinkDeployment(deployment) => {
+            let spec = &deployment.spec;
+            v.check(!spec.image.is_empty(), "spec.image", "is required");
+            v.check(
+                FLINK_VERSION.is_match(&spec.flink_version),
+                "spec.flinkVersion",
+                "must look like v1_17",
+            );
+            v.component(&spec.job_manager, "spec.jobManager");
+            v.component(&spec.task_manager, "spec.taskManager");
+            if let Some(job) = &spec.job {
+                let configured = |keys: &[&str]| {
+                    keys.iter()
+                        .any(|key| spec.flink_configuration.contains_key(*key))
+                };
+                v.check(
+                    job.upgrade_mode == UpgradeMode::Stateless || configured(&SAVEPOINT_DIR_KEYS),
+                    "spec.flinkConfiguration",
+                    "state.savepoints.dir is required unless upgradeMode is stateless",
+                );
+                v.check(
+                    job.upgrade_mode != UpgradeMode::LastState || configured(&HA_KEYS),
+                    "spec.flinkConfiguration",
+                    "high-availability must be enabled for upgradeMode last-state",
+                );
+            }
+        }
+        OperatorResource::FlinkSessionJob(session_job) => {
+            v.check(
+                !session_job.spec.deployment_name.is_empty(),
+                "spec.deploymentName",
+                "is required",
+            );
+        }
+    }
+    v.issues
+}
+
+/// 默认的 FlinkDeployment 模板，生成时以它为基础
+pub fn default_spec(image: &str, flink_version: &str) -> FlinkDeploymentSpec {
+    let component = ComponentSpec {
+        resource: ResourceSpec {
+            memory: "2048m".to_string(),
+            cpu: 1.0,
+        },
+        replicas: None,
+    };
+    FlinkDeploymentSpec {
+        image: image.to_string(),
+        flink_version: flink_version.to_string(),
+        service_account: Some("flink".to_string()),
+        job_manager: component.clone(),
+        task_manager: component,
+        ..FlinkDeploymentSpec::default()
+    }
+}
+
+/// 把对账 manifest 中的作业转换为 Application 模式的 FlinkDeployment
+///
+/// 本地 jar 路径会被替换为镜像中 `/opt/flink/usrlib` 下的同名文件；
+/// savepoint 策略为 `none` 时使用 stateless 升级，否则使用 savepoint 升级。
+pub fn deployment_for(
+    job: &DesiredJob,
+    template: &FlinkDeploymentSpec,
+    savepoint_dir: Option<&str>,
+    namespace: Option<&str>,
+) -> FlinkDeployment {
+    let jar_uri = if job.jar.contains("://") {
+        job.jar.clone()
+    } else {
+        let file = Path::new(&job.jar)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(&job.jar);
+        format!("{}/{}", USRLIB, file)
+    };
+    let upgrade_mode = match job.savepoint_policy {
+        SavepointPolicy::None => UpgradeMode::Stateless,
+        SavepointPolicy::Required | SavepointPolicy::BestEffort => UpgradeMode::Savepoint,
+    };
+    let mut spec = template.clone();
+    spec.flink_configuration.extend(job.config.clone());
+    if let Some(dir) = job.savepoint_dir.as_deref().or(savepoint_dir) {
+        spec.flink_configuration
+            .insert(SAVEPOINT_DIR_KEYS[0].to_string(), dir.to_string());
+    }
+    spec.job = Some(JobSpec {
+        jar_uri,
+        entry_class: job.entry_class.clone(),
+        args: job.args.clone(),
+        parallelism: job.parallelism,
+        upgrade_mode,
+        ..JobSpec::default()
+    });
+    FlinkDeployment {
+        api_version: API_VERSION.to_string(),
+        metadata: ObjectMeta {
+            name: resource_name(&job.name),
+            namespace: namespace.map(|ns| ns.to_string()),
+            ..ObjectMeta::default()
+        },
+        spec,
+        status: None,
+    }
+}
+
+/// 作业名称转换为合法的 Kubernetes 资源名称
+fn resource_name(name: &str) -> String {
+    let name: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let name: Vec<&str> = name.split('-').filter(|part| !part.is_empty()).collect();
+    let name = name.join("-");
+    name.chars()
+        .take(63)
+        .collect::<String>()
+        .trim_end_matches('-')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::enums::JobState;
+
+    const MANIFEST: &str = r#"
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: unrelated
+---
+apiVersion: flink.apache.org/v1beta1
+kind: FlinkDeployment
+metadata:
+  name: orders-etl
+  namespace: flink
+spec:
+  image: flink:1.17
+  flinkVersion: v1_17
+  flinkConfiguration:
+    taskmanager.numberOfTaskSlots: "2"
+  jobManager:
+    resource: {memory: 2048m, cpu: 1}
+  taskManager:
+    resource: {memory: 4Gi, cpu: 2}
+  job:
+    jarURI: local:///opt/flink/usrlib/orders.jar
+    parallelism: 4
+    upgradeMode: savepoint
+status:
+  lifecycleState: STABLE
+  jobManagerDeploymentStatus: READY
+  jobStatus:
+    jobName: orders etl
+    jobId: a1b2c3
+    state: RUNNING
+    startTime: "1700000000000"
+    updateTime: "1700000100000"
+---
+apiVersion: flink.apache.org/v1beta1
+kind: FlinkSessionJob
+metadata:
+  name: Payments
+spec:
+  job:
+    jarURI: ""
+    upgradeMode: last-state
+status:
+  lifecycleState: SUSPENDED
+"#;
+
+    #[test]
+    fn test_parse_and_map_status() {
+        let resources = parse_manifests(MANIFEST, "test.yaml").unwrap();
+        assert_eq!(resources.len(), 2);
+
+        let jobs = jobs(&resources);
+        assert_eq!(jobs[0].jid, "a1b2c3");
+        assert_eq!(jobs[0].name, "orders etl");
+        assert_eq!(jobs[0].job_state(), JobState::Running);
+        assert_eq!(jobs[0].start_time, Some(1_700_000_000_000));
+        assert_eq!(jobs[1].name, "Payments");
+        assert_eq!(jobs[1].job_state(), JobState::Suspended);
+
+        // 生成后再解析，内容保持一致
+        let yaml = to_yaml(&resources).unwrap();
+        let reparsed = parse_manifests(&yaml, "generated.yaml").unwrap();
+        assert_eq!(
+            serde_json::to_value(&jobs).unwrap(),
+            serde_json::to_value(self::jobs(&reparsed)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let resources = parse_manifests(MANIFEST, "test.yaml").unwrap();
+        let fields = |r| -> Vec<String> { validate(r).into_iter().map(|i| i.field).collect() };
+        assert_eq!(fields(&resources[0]), vec!["spec.flinkConfiguration"]);
+        assert_eq!(
+            fields(&resources[1]),
+            vec!["metadata.name", "spec.job.jarURI", "spec.deploymentName"]
+        );
+    }
+
+    #[test]
+    fn test_deployment_for() {
+        let job: DesiredJob = toml::from_str(
+            r#"
+            name = "Orders ETL (v2)"
+            jar = "target/orders.jar"
+            parallelism = 2
+            "#,
+        )
+        .unwrap();
+        let deployment = deployment_for(
+            &job,
+            &default_spec("flink:1.17", "v1_17"),
+            Some("s3://savepoints"),
+            None,
+        );
+        assert_eq!(deployment.metadata.name, "orders-etl-v2");
+        let spec_job = deployment.spec.job.as_ref().unwrap();
+        assert_eq!(spec_job.jar_uri, "local:///opt/flink/usrlib/orders.jar");
+        assert_eq!(spec_job.upgrade_mode, UpgradeMode::Savepoint);
+        assert!(validate(&OperatorResource::FlinkDeployment(deployment)).is_empty());
+    }
+}
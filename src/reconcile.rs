@@ -0,0 +1,551 @@
+use crate::client::error::FlinkError;
+use crate::client::flink::FlinkClient;
+use crate::models::flink::JarRunRequest;
+use crate::snapshot::{JobSnapshot, Snapshot};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::thread::sleep;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ReconcileError {
+    #[error(transparent)]
+    Flink(#[from] FlinkError),
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("invalid manifest {path}: {source}")]
+    Toml {
+        path: String,
+        #[source]
+        source: toml::de::Error,
+    },
+    #[error("jar {0} is neither uploaded nor a local file")]
+    JarNotFound(String),
+    #[error("savepoint of job {jid} failed: {message}")]
+    Savepoint { jid: String, message: String },
+    #[error("{} running jobs are named {name} ({}), cancel the extra ones first", jids.len(), jids.join(", "))]
+    Ambiguous { name: String, jids: Vec<String> },
+}
+
+/// 升级作业时如何处理状态
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SavepointPolicy {
+    /// 必须 stop-with-savepoint 成功才会重新部署
+    #[default]
+    Required,
+    /// savepoint 失败时直接取消并无状态启动
+    BestEffort,
+    /// 不做 savepoint，直接取消后重新部署
+    None,
+}
+
+/// 期望状态清单，例如:
+///
+/// ```toml
+/// savepoint_dir = "hdfs:///flink/savepoints"
+///
+/// [[jobs]]
+/// name = "orders-etl"
+/// jar = "jars/orders-etl-1.2.jar"
+/// entry_class = "com.example.OrdersEtl"
+/// args = ["--env", "prod"]
+/// parallelism = 4
+/// savepoint_policy = "required"
+/// allow_non_restored_state = false
+///
+/// [jobs.config]
+/// "execution.checkpointing.interval" = "1 min"
+/// ```
+///
+/// `name` 必须与程序中 `execute(name)` 设置的作业名一致。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub savepoint_dir: Option<String>,
+    #[serde(default = "default_savepoint_timeout_secs")]
+    pub savepoint_timeout_secs: u64,
+    #[serde(default)]
+    pub allow_cancel_unmanaged: bool,
+    #[serde(default)]
+    pub jobs: Vec<DesiredJob>,
+}
+
+fn default_savepoint_timeout_secs() -> u64 {
+    600
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DesiredJob {
+    pub name: String,
+    /// 已上传 jar 的文件名，或者本地 jar 路径（还没有上传或本地文件更新时会自动上传）
+    pub jar: String,
+    #[serde(default)]
+    pub entry_class: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub parallelism: Option<i32>,
+    #[serde(default)]
+    pub savepoint_policy: SavepointPolicy,
+    #[serde(default)]
+    pub savepoint_dir: Option<String>,
+    /// 从 savepoint 恢复时允许跳过新版本中已删除的算子的状态
+    #[serde(default)]
+    pub allow_non_restored_state: bool,
+    /// 提交时传给 Flink 的作业配置，覆盖集群配置
+    #[serde(default)]
+    pub config: BTreeMap<String, String>,
+}
+
+impl DesiredJob {
+    fn jar_file_name(&self) -> &str {
+        Path::new(&self.jar)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(&self.jar)
+    }
+
+    /// 本地 jar 的修改时间（毫秒），不是本地文件时为 `None`
+    fn jar_modified(&self) -> Option<i64> {
+        let metadata = fs::metadata(&self.jar).ok().filter(|m| m.is_file())?;
+        let modified = metadata.modified().ok()?;
+        let millis = modified.duration_since(UNIX_EPOCH).ok()?.as_millis();
+        i64::try_from(millis).ok()
+    }
+}
+
+impl Manifest {
+    pub fn load(path: &str) -> Result<Manifest, ReconcileError> {
+        let content = fs::read_to_string(path).map_err(|source| ReconcileError::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        toml::from_str(&content).map_err(|source| ReconcileError::Toml {
+            path: path.to_string(),
+            source,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Action {
+    Start(DesiredJob),
+    Upgrade {
+        job: DesiredJob,
+        jid: String,
+        reasons: Vec<String>,
+    },
+    Cancel {
+        name: String,
+        jid: String,
+    },
+    /// 不在清单中的运行作业，未允许取消时只做提示
+    KeepUnmanaged {
+        name: String,
+        jid: String,
+    },
+    Unchanged {
+        name: String,
+    },
+}
+
+impl Action {
+    pub fn is_change(&self) -> bool {
+        !matches!(
+            self,
+            Action::KeepUnmanaged { .. } | Action::Unchanged { .. }
+        )
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Start(job) => write!(f, "+ start {} ({})", job.name, job.jar_file_name()),
+            Action::Upgrade { job, jid, reasons } => write!(
+                f,
+                "~ upgrade {} [{}] via {:?} savepoint: {}",
+                job.name,
+                jid,
+                job.savepoint_policy,
+                reasons.join(", ")
+            ),
+            Action::Cancel { name, jid } => write!(f, "- cancel unmanaged {} [{}]", name, jid),
+            Action::KeepUnmanaged { name, jid } => {
+                write!(f, "? keep unmanaged {} [{}]", name, jid)
+            }
+            Action::Unchanged { name } => write!(f, "= {} is up to date", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Plan {
+    pub cluster: String,
+    pub actions: Vec<Action>,
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let changes = self.actions.iter().filter(|a| a.is_change()).count();
+        writeln!(f, "plan for {}: {} change(s)", self.cluster, changes)?;
+        for action in &self.actions {
+            writeln!(f, "  {}", action)?;
+        }
+        Ok(())
+    }
+}
+
+/// 对比清单与集群快照（需要包含作业配置），生成执行计划；
+/// 有多个同名的运行中作业时无法确定升级或取消哪一个，返回 [`ReconcileError::Ambiguous`]
+pub fn plan(
+    manifest: &Manifest,
+    snapshot: &Snapshot,
+    allow_cancel_unmanaged: bool,
+) -> Result<Plan, ReconcileError> {
+    let mut by_name: BTreeMap<&str, Vec<&JobSnapshot>> = BTreeMap::new();
+    for job in snapshot
+        .jobs
+        .iter()
+        .filter(|job| !job.job.job_state().is_terminal())
+    {
+        by_name.entry(job.job.name.as_str()).or_default().push(job);
+    }
+    if let Some((name, jobs)) = by_name.iter().find(|(_, jobs)| jobs.len() > 1) {
+        return Err(ReconcileError::Ambiguous {
+            name: name.to_string(),
+            jids: jobs.iter().map(|job| job.job.jid.clone()).collect(),
+        });
+    }
+    let running: Vec<&JobSnapshot> = by_name.into_values().flatten().collect();
+    let mut actions = Vec::new();
+
+    for desired in &manifest.jobs {
+        let Some(current) = running.iter().find(|job| job.job.name == desired.name) else {
+            actions.push(Action::Start(desired.clone()));
+            continue;
+        };
+        let reasons = upgrade_reasons(desired, current);
+        if reasons.is_empty() {
+            actions.push(Action::Unchanged {
+                name: desired.name.clone(),
+            });
+        } else {
+            actions.push(Action::Upgrade {
+                job: desired.clone(),
+                jid: current.job.jid.clone(),
+                reasons,
+            });
+        }
+    }
+
+    let allow_cancel = allow_cancel_unmanaged || manifest.allow_cancel_unmanaged;
+    for job in running {
+        if manifest
+            .jobs
+            .iter()
+            .any(|desired| desired.name == job.job.name)
+        {
+            continue;
+        }
+        let (name, jid) = (job.job.name.clone(), job.job.jid.clone());
+        actions.push(if allow_cancel {
+            Action::Cancel { name, jid }
+        } else {
+            Action::KeepUnmanaged { name, jid }
+        });
+    }
+
+    Ok(Plan {
+        cluster: snapshot.cluster.clone(),
+        actions,
+    })
+}
+
+/// 无法从集群读到的信息（老版本没有 jar 路径）不作为升级依据；
+/// 同名的本地 jar 在作业启动后被修改过，说明重新构建过，同样需要升级
+fn upgrade_reasons(desired: &DesiredJob, current: &JobSnapshot) -> Vec<String> {
+    let mut reasons = Vec::new();
+    if let Some(jar) = &current.jar
+        && !jar
+            .split(';')
+            .any(|j| is_same_jar(j, desired.jar_file_name()))
+    {
+        reasons.push(format!("jar {} -> {}", jar, desired.jar_file_name()));
+    } else if let (Some(modified), Some(started)) = (desired.jar_modified(), current.job.start_time)
+        && modified > started
+    {
+        reasons.push(format!(
+            "jar {} was rebuilt after the job started",
+            desired.jar
+        ));
+    }
+    if let (Some(want), Some(have)) = (desired.parallelism, current.parallelism())
+        && want != have
+    {
+        reasons.push(format!("parallelism {} -> {}", have, want));
+    }
+    reasons
+}
+
+/// `pipeline.jars` 中的一项是否为 `name`：比较路径的最后一段，
+/// 上传后的文件名带有 `<id>_` 前缀
+fn is_same_jar(path: &str, name: &str) -> bool {
+    let file = path.rsplit('/').next().unwrap_or(path);
+    file == name || file.split_once('_').is_some_and(|(_, rest)| rest == name)
+}
+
+#[derive(Debug)]
+pub struct ActionResult {
+    pub action: Action,
+    pub result: Result<String, ReconcileError>,
+}
+
+/// 依次执行计划中的变更；单个作业失败不影响其它作业
+pub fn apply(client: &FlinkClient, manifest: &Manifest, plan: &Plan) -> Vec<ActionResult> {
+    plan.actions
+        .iter()
+        .filter(|action| action.is_change())
+        .map(|action| ActionResult {
+            action: action.clone(),
+            result: apply_action(client, manifest, action),
+        })
+        .collect()
+}
+
+fn apply_action(
+    client: &FlinkClient,
+    manifest: &Manifest,
+    action: &Action,
+) -> Result<String, ReconcileError> {
+    match action {
+        Action::Start(job) => {
+            let jid = start(client, job, None)?;
+            Ok(format!("started {}", jid))
+        }
+        Action::Upgrade { job, jid, .. } => {
+            let savepoint = match job.savepoint_policy {
+                SavepointPolicy::Required => Some(stop_with_savepoint(client, manifest, job, jid)?),
+                SavepointPolicy::BestEffort => {
+                    match stop_with_savepoint(client, manifest, job, jid) {
+                        Ok(location) => Some(location),
+                        Err(_) => {
+                            client.cancel_job(jid)?;
+                            None
+                        }
+                    }
+                }
+                SavepointPolicy::None => {
+                    client.cancel_job(jid)?;
+                    None
+                }
+            };
+            let new_jid = start(client, job, savepoint.clone())?;
+            Ok(match savepoint {
+                Some(location) => format!("restarted as {} from {}", new_jid, location),
+                None => format!("restarted as {} without state", new_jid),
+            })
+        }
+        Action::Cancel { jid, .. } => {
+            client.cancel_job(jid)?;
+            Ok(String::from("canceled"))
+        }
+        Action::KeepUnmanaged { .. } | Action::Unchanged { .. } => Ok(String::new()),
+    }
+}
+
+fn start(
+    client: &FlinkClient,
+    job: &DesiredJob,
+    savepoint_path: Option<String>,
+) -> Result<String, ReconcileError> {
+    let jar_id = resolve_jar(client, job)?;
+    let request = JarRunRequest {
+        entry_class: job.entry_class.clone(),
+        program_args_list: job.args.clone(),
+        parallelism: job.parallelism,
+        savepoint_path,
+        allow_non_restored_state: job.allow_non_restored_state,
+        flink_configuration: job.config.clone(),
+    };
+    Ok(client.run_jar(&jar_id, &request)?)
+}
+
+/// 本地文件比最近上传的同名 jar 新（或者还没有上传过）时上传本地文件，否则使用已上传的 jar
+fn resolve_jar(client: &FlinkClient, job: &DesiredJob) -> Result<String, ReconcileError> {
+    let uploaded = client
+        .list_jars()?
+        .files
+        .into_iter()
+        .filter(|jar| jar.name == job.jar_file_name())
+        .max_by_key(|jar| jar.uploaded);
+    match (uploaded, job.jar_modified()) {
+        (Some(jar), Some(modified)) if jar.uploaded >= modified => Ok(jar.id),
+        (_, Some(_)) => Ok(client.upload_jar(&job.jar)?),
+        (Some(jar), None) => Ok(jar.id),
+        (None, None) => Err(ReconcileError::JarNotFound(job.jar.clone())),
+    }
+}
+
+fn stop_with_savepoint(
+    client: &FlinkClient,
+    manifest: &Manifest,
+    job: &DesiredJob,
+    jid: &str,
+) -> Result<String, ReconcileError> {
+    let target = job
+        .savepoint_dir
+        .as_ref()
+        .or(manifest.savepoint_dir.as_ref());
+    let trigger = client.stop_with_savepoint(jid, target.map(|s| s.as_str()), false)?;
+    let deadline = Instant::now() + Duration::from_secs(manifest.savepoint_timeout_secs);
+    let failed = |message: String| ReconcileError::Savepoint {
+        jid: jid.to_string(),
+        message,
+    };
+    loop {
+        // 网络抖动或 5xx 时继续轮询到超时为止，savepoint 可能已经在进行
+        let status = match client.savepoint_status(jid, &trigger) {
+            Ok(status) => Some(status),
+            Err(e) if e.is_retryable() && Instant::now() < deadline => None,
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(status) = status
+            && status.status.id == "COMPLETED"
+        {
+            let operation = status
+                .operation
+                .ok_or_else(|| failed("empty result".into()))?;
+            if let Some(location) = operation.location {
+                return Ok(location);
+            }
+            let cause = operation.failure_cause.unwrap_or_default();
+            return Err(failed(cause.to_string()));
+        }
+        if Instant::now() > deadline {
+            return Err(failed(format!(
+                "timed out after {}s",
+                manifest.savepoint_timeout_secs
+            )));
+        }
+        sleep(Duration::from_secs(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn running(name: &str, parallelism: i32, jar: &str) -> JobSnapshot {
+        let json = format!(
+            r#"{{"jid":"{name}-id","name":"{name}","state":"RUNNING","start-time":1,
+                "tasks":{{"total":1,"created":0,"scheduled":0,"deploying":0,"running":1,"finished":0,
+                "canceling":0,"canceled":0,"failed":0,"reconciling":0,"initializing":0}}}}"#
+        );
+        let config = format!(
+            r#"{{"jid":"x","name":"{name}","execution-config":{{"job-parallelism":{parallelism}}}}}"#
+        );
+        JobSnapshot {
+            job: serde_json::from_str(&json).unwrap(),
+            config: Some(serde_json::from_str(&config).unwrap()),
+            jar: Some(format!("file:/tmp/flink-web/upload/0a1b_{jar}")),
+        }
+    }
+
+    const MANIFEST: &str = r#"
+        [[jobs]]
+        name = "orders"
+        jar = "jars/orders-1.1.jar"
+        parallelism = 4
+
+        [[jobs]]
+        name = "payments"
+        jar = "payments-2.0.jar"
+        parallelism = 2
+
+        [[jobs]]
+        name = "search"
+        jar = "search.jar"
+    "#;
+
+    #[test]
+    fn test_plan() {
+        let manifest: Manifest = toml::from_str(MANIFEST).unwrap();
+        assert_eq!(manifest.jobs[0].savepoint_policy, SavepointPolicy::Required);
+        assert!(!manifest.jobs[0].allow_non_restored_state);
+        let snapshot = Snapshot {
+            cluster: "prod".to_string(),
+            taken_at: 0,
+            jobs: vec![
+                running("orders", 2, "orders-1.0.jar"),
+                running("payments", 2, "payments-2.0.jar"),
+                running("adhoc", 1, "adhoc.jar"),
+            ],
+        };
+
+        let plan = plan(&manifest, &snapshot, false).unwrap();
+        let lines: Vec<String> = plan.actions.iter().map(|a| a.to_string()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "~ upgrade orders [orders-id] via Required savepoint: \
+                 jar file:/tmp/flink-web/upload/0a1b_orders-1.0.jar -> orders-1.1.jar, \
+                 parallelism 2 -> 4",
+                "= payments is up to date",
+                "+ start search (search.jar)",
+                "? keep unmanaged adhoc [adhoc-id]",
+            ]
+        );
+
+        let plan = super::plan(&manifest, &snapshot, true).unwrap();
+        assert!(matches!(plan.actions[3], Action::Cancel { .. }));
+
+        // 两个同名的运行中作业不能随便选一个
+        let mut duplicate = snapshot.clone();
+        let mut second = running("orders", 2, "orders-1.0.jar");
+        second.job.jid = String::from("orders-2");
+        duplicate.jobs.push(second);
+        match super::plan(&manifest, &duplicate, false) {
+            Err(ReconcileError::Ambiguous { name, jids }) => {
+                assert_eq!(name, "orders");
+                assert_eq!(jids, vec!["orders-id", "orders-2"]);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // 同名的本地 jar 在作业启动后重新构建过
+        let dir =
+            std::env::temp_dir().join(format!("rust_project_reconcile_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let jar = dir.join("payments-2.0.jar");
+        fs::write(&jar, b"PK").unwrap();
+        let mut payments = manifest.jobs[1].clone();
+        payments.jar = jar.to_str().unwrap().to_string();
+        let reasons = upgrade_reasons(&payments, &snapshot.jobs[1]);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(is_same_jar(
+            "file:/tmp/upload/0a1b_orders.jar",
+            "orders.jar"
+        ));
+        assert!(!is_same_jar(
+            "file:/tmp/upload/0a1b_xorders.jar",
+            "orders.jar"
+        ));
+        assert_eq!(
+            reasons,
+            vec![format!(
+                "jar {} was rebuilt after the job started",
+                jar.display()
+            )]
+        );
+    }
+}
@@ -0,0 +1,489 @@
+use crate::models::enums::JobState;
+use crate::models::flink::Flink;
+use crate::naming::{JobFilter, NamingConvention};
+use chrono::{DateTime, Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use thiserror::Error;
+
+/// 两次轮询间隔超过该值时（采集进程停止、网络中断），中间的时间不计入任何状态
+pub const MAX_GAP_MS: i64 = 10 * 60 * 1000;
+
+const DAY_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Error)]
+pub enum SlaError {
+    #[error("failed to access {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("invalid sla store {path}: {source}")]
+    Json {
+        path: String,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+/// 状态在可用性统计中的归类
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Availability {
+    Up,
+    /// RESTARTING / FAILING
+    Restarting,
+    Failed,
+    /// 启动中、RECONCILING 等过渡状态，同样算作不可用
+    Other,
+    /// 主动取消或正常结束，不计入统计
+    Stopped,
+}
+
+impl From<JobState> for Availability {
+    fn from(state: JobState) -> Self {
+        match state {
+            JobState::Running => Availability::Up,
+            JobState::Restarting | JobState::Failing => Availability::Restarting,
+            JobState::Failed => Availability::Failed,
+            JobState::Cancelling
+            | JobState::Canceled
+            | JobState::Finished
+            | JobState::Suspended => Availability::Stopped,
+            JobState::Initializing
+            | JobState::Created
+            | JobState::Reconciling
+            | JobState::Unknown => Availability::Other,
+        }
+    }
+}
+
+/// 某个作业一天（UTC）内的累计数据，时间单位为毫秒
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Period {
+    pub running_ms: i64,
+    pub restarting_ms: i64,
+    pub failed_ms: i64,
+    pub other_ms: i64,
+    pub stopped_ms: i64,
+    pub restarts: u32,
+    pub recoveries: u32,
+    pub recovery_ms: i64,
+}
+
+impl Period {
+    fn add_time(&mut self, availability: Availability, ms: i64) {
+        match availability {
+            Availability::Up => self.running_ms += ms,
+            Availability::Restarting => self.restarting_ms += ms,
+            Availability::Failed => self.failed_ms += ms,
+            Availability::Other => self.other_ms += ms,
+            Availability::Stopped => self.stopped_ms += ms,
+        }
+    }
+
+    fn merge(&mut self, other: &Period) {
+        self.running_ms += other.running_ms;
+        self.restarting_ms += other.restarting_ms;
+        self.failed_ms += other.failed_ms;
+        self.other_ms += other.other_ms;
+        self.stopped_ms += other.stopped_ms;
+        self.restarts += other.restarts;
+        self.recoveries += other.recoveries;
+        self.recovery_ms += other.recovery_ms;
+    }
+
+    pub fn down_ms(&self) -> i64 {
+        self.restarting_ms + self.failed_ms + self.other_ms
+    }
+
+    /// RUNNING 时间占应当运行时间的比例，没有任何数据时返回 `None`
+    pub fn availability(&self) -> Option<f64> {
+        let total = self.running_ms + self.down_ms();
+        (total > 0).then(|| self.running_ms as f64 / total as f64)
+    }
+
+    /// 平均恢复时间（MTTR）
+    pub fn mttr_ms(&self) -> Option<i64> {
+        (self.recoveries > 0).then(|| self.recovery_ms / self.recoveries as i64)
+    }
+}
+
+/// 单个作业的采集状态和按天汇总的数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobSla {
+    pub last_state: JobState,
+    pub last_seen: i64,
+    /// 最近一次从可用变为 RESTARTING / FAILED 的时间，恢复 RUNNING 后清空
+    #[serde(default)]
+    pub down_since: Option<i64>,
+    /// 键为 `YYYY-MM-DD`（UTC）
+    #[serde(default)]
+    pub days: BTreeMap<String, Period>,
+}
+
+impl JobSla {
+    /// 把 `[from, to)` 的时间记到对应状态上，跨天时拆分到每一天
+    fn add_time(&mut self, availability: Availability, from: i64, to: i64) {
+        let mut start = from;
+        while start < to {
+            let Some(time) = DateTime::from_timestamp_millis(start) else {
+                return;
+            };
+            let date = time.date_naive();
+            let end = date
+                .checked_add_days(Days::new(1))
+                .and_then(|next| next.and_hms_opt(0, 0, 0))
+                .map_or(to, |midnight| midnight.and_utc().timestamp_millis().min(to));
+            self.period(date).add_time(availability, end - start);
+            start = end;
+        }
+    }
+
+    fn period(&mut self, date: NaiveDate) -> &mut Period {
+        self.days
+            .entry(date.format(DAY_FORMAT).to_string())
+            .or_default()
+    }
+
+    fn period_at(&mut self, timestamp: i64) -> Option<&mut Period> {
+        let date = DateTime::from_timestamp_millis(timestamp)?.date_naive();
+        Some(self.period(date))
+    }
+}
+
+/// 基于 `/jobs/overview` 轮询结果累计的可用性数据，以 JSON 文件保存在本地
+///
+/// 作业按名称统计，重新提交（jid 变化）的作业会接着之前的数据累计。
+/// 重启次数根据进入 RESTARTING 的次数计算，轮询间隔内完成的重启无法被观察到。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SlaStore {
+    #[serde(default)]
+    pub jobs: BTreeMap<String, JobSla>,
+}
+
+impl SlaStore {
+    /// 文件不存在时返回空的存储
+    pub fn load(path: &str) -> Result<SlaStore, SlaError> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(SlaStore::default());
+            }
+            Err(source) => {
+                return Err(SlaError::Io {
+                    path: path.to_string(),
+                    source,
+                });
+            }
+        };
+        serde_json::from_str(&content).map_err(|source| SlaError::Json {
+            path: path.to_string(),
+            source,
+        })
+    }
+
+    /// 先写临时文件再重命名，避免采集进程被中断时留下不完整的文件
+    pub fn save(&self, path: &str) -> Result<(), SlaError> {
+        let json = serde_json::to_string_pretty(self).map_err(|source| SlaError::Json {
+            path: path.to_string(),
+            source,
+        })?;
+        let tmp = format!("{}.tmp", path);
+        fs::write(&tmp, json)
+            .and_then(|_| fs::rename(&tmp, path))
+            .map_err(|source| SlaError::Io {
+                path: path.to_string(),
+                source,
+            })
+    }
+
+    /// 记录一次轮询结果，`now` 为毫秒时间戳
+    pub fn record(&mut self, jobs: &[Flink], now: i64) {
+        for (name, job) in Flink::latest_by_name(jobs) {
+            let state = job.job_state();
+            let Some(sla) = self.jobs.get_mut(name) else {
+                let down = matches!(
+                    Availability::from(state),
+                    Availability::Restarting | Availability::Failed
+                );
+                self.jobs.insert(
+                    name.to_string(),
+                    JobSla {
+                        last_state: state,
+                        last_seen: now,
+                        down_since: down.then_some(now),
+                        days: BTreeMap::new(),
+                    },
+                );
+                continue;
+            };
+            let elapsed = now - sla.last_seen;
+            if elapsed <= 0 {
+                continue;
+            }
+            if elapsed <= MAX_GAP_MS {
+                sla.add_time(sla.last_state.into(), sla.last_seen, now);
+            }
+
+            let before = Availability::from(sla.last_state);
+            let after = Availability::from(state);
+            if state == JobState::Restarting
+                && sla.last_state != JobState::Restarting
+                && let Some(period) = sla.period_at(now)
+            {
+                period.restarts += 1;
+            }
+            match after {
+                Availability::Restarting | Availability::Failed
+                    if sla.down_since.is_none() && before != after =>
+                {
+                    sla.down_since = Some(now);
+                }
+                Availability::Up => {
+                    if let Some(since) = sla.down_since.take()
+                        && let Some(period) = sla.period_at(now)
+                    {
+                        period.recoveries += 1;
+                        period.recovery_ms += now - since;
+                    }
+                }
+                // 失败后被手动取消不算恢复
+                Availability::Stopped => sla.down_since = None,
+                _ => {}
+            }
+            sla.last_state = state;
+            sla.last_seen = now;
+        }
+    }
+
+    /// 汇总 `[from, to]`（含两端，UTC 日期）内每个作业的数据
+    pub fn report(&self, from: NaiveDate, to: NaiveDate) -> SlaReport {
+        let from_key = from.format(DAY_FORMAT).to_string();
+        let to_key = to.format(DAY_FORMAT).to_string();
+        let rows = self
+            .jobs
+            .iter()
+            .filter_map(|(name, sla)| {
+                let mut total = Period::default();
+                let mut found = false;
+                for (_, period) in sla.days.range(from_key.clone()..=to_key.clone()) {
+                    total.merge(period);
+                    found = true;
+                }
+                found.then(|| SlaRow {
+                    name: name.clone(),
+                    period: total,
+                })
+            })
+            .collect();
+        SlaReport {
+            from,
+            to,
+            group_by: None,
+            rows,
+        }
+    }
+
+    /// 截止到 `to`（含）的最近 7 天
+    pub fn weekly_report(&self, to: NaiveDate) -> SlaReport {
+        let from = to.checked_sub_days(Days::new(6)).unwrap_or(to);
+        self.report(from, to)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SlaRow {
+    pub name: String,
+    pub period: Period,
+}
+
+#[derive(Debug, Clone)]
+pub struct SlaReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// 按元数据字段分组时的字段名，此时每行的 `name` 为该字段的值
+    pub group_by: Option<String>,
+    pub rows: Vec<SlaRow>,
+}
+
+impl SlaReport {
+    pub fn filter(mut self, filter: &JobFilter, convention: &NamingConvention) -> SlaReport {
+        self.rows
+            .retain(|row| filter.matches(&row.name, &convention.parse(&row.name)));
+        self
+    }
+
+    /// 按作业名称中解析出的字段（例如 team）汇总
+    pub fn group_by(self, convention: &NamingConvention, field: &str) -> SlaReport {
+        let mut groups: BTreeMap<String, Period> = BTreeMap::new();
+        for row in &self.rows {
+            groups
+                .entry(convention.group(&row.name, field))
+                .or_default()
+                .merge(&row.period);
+        }
+        SlaReport {
+            group_by: Some(field.to_string()),
+            rows: groups
+                .into_iter()
+                .map(|(name, period)| SlaRow { name, period })
+                .collect(),
+            ..self
+        }
+    }
+
+    fn label(&self) -> &str {
+        self.group_by.as_deref().unwrap_or("job")
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# Flink SLA report {} ~ {}\n", self.from, self.to);
+        let _ = writeln!(
+            out,
+            "| {} | availability | running | down | restarts | recoveries | MTTR |",
+            self.label()
+        );
+        out.push_str("|---|---:|---:|---:|---:|---:|---:|\n");
+        for row in &self.rows {
+            let period = &row.period;
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} | {} | {} | {} |",
+                row.name.replace('|', "\\|"),
+                period
+                    .availability()
+                    .map_or("-".to_string(), |a| format!("{:.3}%", a * 100.0)),
+                format_duration(period.running_ms),
+                format_duration(period.down_ms()),
+                period.restarts,
+                period.recoveries,
+                period.mttr_ms().map_or("-".to_string(), format_duration),
+            );
+        }
+        out
+    }
+
+    /// 时间列以毫秒输出，便于导入表格后自行计算
+    pub fn to_csv(&self) -> String {
+        let mut out = format!(
+            "{},availability,running_ms,restarting_ms,failed_ms,other_ms,restarts,recoveries,mttr_ms\n",
+            csv_field(self.label())
+        );
+        for row in &self.rows {
+            let period = &row.period;
+            let _ = writeln!(
+                out,
+                "{},{},{},{},{},{},{},{},{}",
+                csv_field(&row.name),
+                period
+                    .availability()
+                    .map_or(String::new(), |a| format!("{:.6}", a)),
+                period.running_ms,
+                period.restarting_ms,
+                period.failed_ms,
+                period.other_ms,
+                period.restarts,
+                period.recoveries,
+                period.mttr_ms().map_or(String::new(), |ms| ms.to_string()),
+            );
+        }
+        out
+    }
+}
+
+fn format_duration(ms: i64) -> String {
+    let seconds = ms / 1000;
+    match seconds {
+        s if s < 60 => format!("{}s", s),
+        s if s < 3600 => format!("{}m {:02}s", s / 60, s % 60),
+        s => format!("{}h {:02}m", s / 3600, s % 3600 / 60),
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::flink::TaskInfo;
+    use crate::naming::JobMetadata;
+
+    const MINUTE: i64 = 60 * 1000;
+
+    fn job(name: &str, state: &str) -> Flink {
+        Flink {
+            jid: "j1".to_string(),
+            name: name.to_string(),
+            state: state.to_string(),
+            start_time: Some(0),
+            end_time: None,
+            duration: None,
+            last_modification: None,
+            tasks: TaskInfo::default(),
+            metadata: JobMetadata::default(),
+        }
+    }
+
+    #[test]
+    fn test_record_restart_and_recovery() {
+        // 2024-01-01T23:58:00Z
+        let start = 1_704_153_480_000;
+        let mut store = SlaStore::default();
+        store.record(&[job("orders", "RUNNING")], start);
+        store.record(&[job("orders", "RESTARTING")], start + MINUTE);
+        store.record(&[job("orders", "RUNNING")], start + 4 * MINUTE);
+        store.record(&[job("orders", "RUNNING")], start + 5 * MINUTE);
+
+        let sla = &store.jobs["orders"];
+        let first = &sla.days["2024-01-01"];
+        assert_eq!(first.running_ms, MINUTE);
+        assert_eq!(first.restarting_ms, MINUTE);
+        assert_eq!(first.restarts, 1);
+        let second = &sla.days["2024-01-02"];
+        assert_eq!(second.restarting_ms, 2 * MINUTE);
+        assert_eq!(second.running_ms, MINUTE);
+        assert_eq!(second.recoveries, 1);
+        assert_eq!(second.mttr_ms(), Some(3 * MINUTE));
+
+        let report = store.report(
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+        );
+        assert_eq!(report.rows[0].period.availability(), Some(0.4));
+        assert_eq!(
+            report.to_csv().lines().nth(1),
+            Some("orders,0.400000,120000,180000,0,0,1,1,180000")
+        );
+        assert!(
+            report
+                .to_markdown()
+                .contains("| orders | 40.000% | 2m 00s | 3m 00s | 1 | 1 | 3m 00s |")
+        );
+    }
+
+    #[test]
+    fn test_gap_and_cancel_are_not_counted() {
+        let start = 1_704_153_480_000;
+        let mut store = SlaStore::default();
+        store.record(&[job("orders", "FAILED")], start);
+        store.record(&[job("orders", "FAILED")], start + MAX_GAP_MS + 1);
+        store.record(&[job("orders", "CANCELED")], start + MAX_GAP_MS + MINUTE);
+        store.record(&[job("orders", "RUNNING")], start + MAX_GAP_MS + 2 * MINUTE);
+
+        let total = store.weekly_report(NaiveDate::from_ymd_opt(2024, 1, 7).unwrap());
+        let period = &total.rows[0].period;
+        assert_eq!(period.failed_ms, MINUTE - 1);
+        assert_eq!(period.stopped_ms, MINUTE);
+        assert_eq!(period.recoveries, 0);
+    }
+}
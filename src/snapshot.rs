@@ -0,0 +1,330 @@
+use crate::client::error::FlinkError;
+use crate::client::flink::FlinkClient;
+use crate::models::flink::{Flink, JobConfig};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error(transparent)]
+    Flink(#[from] FlinkError),
+    #[error("failed to access {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("invalid snapshot file {path}: {source}")]
+    Json {
+        path: String,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+/// 某一时刻某个集群 `/jobs/overview` 及作业配置的快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub cluster: String,
+    pub taken_at: i64,
+    pub jobs: Vec<JobSnapshot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobSnapshot {
+    pub job: Flink,
+    #[serde(default)]
+    pub config: Option<JobConfig>,
+    #[serde(default)]
+    pub jar: Option<String>,
+}
+
+impl AsRef<Flink> for JobSnapshot {
+    fn as_ref(&self) -> &Flink {
+        &self.job
+    }
+}
+
+impl JobSnapshot {
+    pub fn parallelism(&self) -> Option<i32> {
+        self.config
+            .as_ref()
+            .and_then(|c| c.execution_config.as_ref())
+            .map(|e| e.job_parallelism)
+    }
+}
+
+impl Snapshot {
+    /// 拉取集群当前的作业列表；`with_config` 为 true 时逐个拉取作业配置
+    pub fn capture(client: &FlinkClient, with_config: bool) -> Result<Snapshot, FlinkError> {
+        let jobs = client
+            .jobs_overview()?
+            .into_iter()
+            .map(|job| {
+                if !with_config {
+                    return Ok(JobSnapshot {
+                        job,
+                        config: None,
+                        jar: None,
+                    });
+                }
+                // 老版本没有 jobmanager/config，已归档的作业也可能取不到配置，404 时记为没有；
+                // 超时和 5xx 要报错，否则会在对比时显示成配置被删除
+                let config = absent_if_not_found(client.job_config(&job.jid))?;
+                let jar = absent_if_not_found(client.job_jobmanager_config(&job.jid))?.and_then(
+                    |entries| {
+                        entries
+                            .into_iter()
+                            .find(|e| e.key == "pipeline.jars")
+                            .map(|e| e.value)
+                    },
+                );
+                Ok(JobSnapshot { job, config, jar })
+            })
+            .collect::<Result<_, FlinkError>>()?;
+        Ok(Snapshot {
+            cluster: client.cluster().name.clone(),
+            taken_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as i64)
+                .unwrap_or_default(),
+            jobs,
+        })
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), SnapshotError> {
+        let json = serde_json::to_string_pretty(self).map_err(|source| SnapshotError::Json {
+            path: path.to_string(),
+            source,
+        })?;
+        fs::write(path, json).map_err(|source| SnapshotError::Io {
+            path: path.to_string(),
+            source,
+        })
+    }
+
+    pub fn load(path: &str) -> Result<Snapshot, SnapshotError> {
+        let content = fs::read_to_string(path).map_err(|source| SnapshotError::Io {
+            path: path.to_string(),
+            source,
+        })?;
+        serde_json::from_str(&content).map_err(|source| SnapshotError::Json {
+            path: path.to_string(),
+            source,
+        })
+    }
+
+    /// 按作业名称索引，规则同 [`Flink::latest_by_name`]
+    pub fn by_name(&self) -> BTreeMap<&str, &JobSnapshot> {
+        Flink::latest_by_name(&self.jobs)
+    }
+}
+
+fn absent_if_not_found<T>(result: Result<T, FlinkError>) -> Result<Option<T>, FlinkError> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.is_not_found() => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct JobChange {
+    pub name: String,
+    pub changes: Vec<FieldChange>,
+}
+
+/// 两个快照之间的差异，作业按名称对应（不同集群、重新部署后 jid 都会变化）
+#[derive(Debug, Clone)]
+pub struct SnapshotDiff {
+    pub before: String,
+    pub after: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<JobChange>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+pub fn diff(before: &Snapshot, after: &Snapshot) -> SnapshotDiff {
+    let old = before.by_name();
+    let new = after.by_name();
+    let added = new
+        .keys()
+        .filter(|name| !old.contains_key(*name))
+        .map(|name| name.to_string())
+        .collect();
+    let removed = old
+        .keys()
+        .filter(|name| !new.contains_key(*name))
+        .map(|name| name.to_string())
+        .collect();
+
+    let mut changed = Vec::new();
+    for (name, old_job) in &old {
+        let Some(new_job) = new.get(name) else {
+            continue;
+        };
+        let mut changes = Vec::new();
+        let mut compare = |field, before: String, after: String| {
+            if before != after {
+                changes.push(FieldChange {
+                    field,
+                    before,
+                    after,
+                });
+            }
+        };
+        compare(
+            "state",
+            old_job.job.state.clone(),
+            new_job.job.state.clone(),
+        );
+        compare(
+            "parallelism",
+            display_option(old_job.parallelism()),
+            display_option(new_job.parallelism()),
+        );
+        compare(
+            "jar",
+            display_option(old_job.jar.as_ref()),
+            display_option(new_job.jar.as_ref()),
+        );
+        if !changes.is_empty() {
+            changed.push(JobChange {
+                name: name.to_string(),
+                changes,
+            });
+        }
+    }
+
+    SnapshotDiff {
+        before: before.cluster.clone(),
+        after: after.cluster.clone(),
+        added,
+        removed,
+        changed,
+    }
+}
+
+fn display_option<T: ToString>(value: Option<T>) -> String {
+    value
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+impl fmt::Display for SnapshotDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "--- {}", self.before)?;
+        writeln!(f, "+++ {}", self.after)?;
+        if self.is_empty() {
+            return writeln!(f, "no differences");
+        }
+        for name in &self.added {
+            writeln!(f, "+ {}", name)?;
+        }
+        for name in &self.removed {
+            writeln!(f, "- {}", name)?;
+        }
+        for job in &self.changed {
+            writeln!(f, "~ {}", job.name)?;
+            for change in &job.changes {
+                writeln!(
+                    f,
+                    "    {}: {} -> {}",
+                    change.field, change.before, change.after
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(name: &str, state: &str, start_time: i64, parallelism: i32) -> JobSnapshot {
+        let json = format!(
+            r#"{{"jid":"{name}-{start_time}","name":"{name}","state":"{state}","start-time":{start_time},
+                "tasks":{{"total":1,"created":0,"scheduled":0,"deploying":0,"running":1,"finished":0,
+                "canceling":0,"canceled":0,"failed":0,"reconciling":0,"initializing":0}}}}"#
+        );
+        let config = format!(
+            r#"{{"jid":"x","name":"{name}","execution-config":{{"job-parallelism":{parallelism}}}}}"#
+        );
+        JobSnapshot {
+            job: serde_json::from_str(&json).unwrap(),
+            config: Some(serde_json::from_str(&config).unwrap()),
+            jar: Some(format!("{name}.jar")),
+        }
+    }
+
+    fn snapshot(cluster: &str, jobs: Vec<JobSnapshot>) -> Snapshot {
+        Snapshot {
+            cluster: cluster.to_string(),
+            taken_at: 0,
+            jobs,
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = snapshot(
+            "test",
+            vec![
+                job("orders", "RUNNING", 1, 2),
+                job("payments", "RUNNING", 1, 1),
+                job("legacy", "RUNNING", 1, 1),
+            ],
+        );
+        let after = snapshot(
+            "prod",
+            vec![
+                job("orders", "CANCELED", 1, 2),
+                job("orders", "RUNNING", 5, 4),
+                job("payments", "FAILED", 1, 1),
+                job("search", "RUNNING", 1, 1),
+            ],
+        );
+
+        let diff = diff(&before, &after);
+        assert_eq!(diff.added, vec!["search"]);
+        assert_eq!(diff.removed, vec!["legacy"]);
+        assert_eq!(diff.changed.len(), 2);
+        assert_eq!(diff.changed[0].name, "orders");
+        assert_eq!(
+            diff.changed[0].changes,
+            vec![FieldChange {
+                field: "parallelism",
+                before: "2".to_string(),
+                after: "4".to_string(),
+            }]
+        );
+        assert_eq!(diff.changed[1].changes[0].field, "state");
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let snapshot = snapshot("test", vec![job("orders", "RUNNING", 1, 2)]);
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let loaded: Snapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.jobs[0].job.start_time, Some(1));
+        assert_eq!(loaded.jobs[0].parallelism(), Some(2));
+    }
+}
@@ -0,0 +1,405 @@
+use crate::models::enums::JobState;
+use crate::models::flink::Flink;
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum StatusLogError {
+    #[error("failed to access {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("invalid record at {path}:{line}: {source}")]
+    Json {
+        path: String,
+        line: usize,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+/// 一次轮询中某个作业的状态，`at` 为采集时间（毫秒）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusRecord {
+    pub at: i64,
+    pub job: Flink,
+}
+
+impl StatusRecord {
+    /// 状态、任务计数和结束时间都没变时认为是重复记录
+    fn same_status(&self, other: &StatusRecord) -> bool {
+        self.job.state == other.job.state
+            && self.job.tasks == other.job.tasks
+            && self.job.end_time == other.job.end_time
+    }
+}
+
+/// 作业在 `[from, to]` 内处于同一状态，`to` 为最后一次观察到该状态的时间
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateSpan {
+    pub jid: String,
+    pub name: String,
+    pub state: JobState,
+    pub from: i64,
+    pub to: i64,
+}
+
+impl fmt::Display for StateSpan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ~ {}  {:<12} {} ({})",
+            format_time(self.from),
+            format_time(self.to),
+            self.state,
+            self.name,
+            self.jid
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompactStats {
+    pub before: usize,
+    pub after: usize,
+}
+
+/// 以 JSON Lines 格式追加保存轮询到的作业状态
+///
+/// 控制台只保留有限数量的已结束作业，这里的记录可以回答“某个作业在 t1 到 t2 之间处于什么状态”。
+/// 每次轮询都会追加完整记录，[`StatusLog::compact`] 负责去掉未发生变化的记录并清理过期数据。
+pub struct StatusLog {
+    path: String,
+}
+
+impl StatusLog {
+    pub fn new(path: &str) -> StatusLog {
+        StatusLog {
+            path: path.to_string(),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn io_error(&self, source: std::io::Error) -> StatusLogError {
+        StatusLogError::Io {
+            path: self.path.clone(),
+            source,
+        }
+    }
+
+    /// 对旁边的 `.lock` 文件加排他锁，`append` 和 `compact` 互斥，锁在返回值 drop 时释放
+    fn lock(&self) -> Result<File, StatusLogError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(format!("{}.lock", self.path))
+            .map_err(|e| self.io_error(e))?;
+        file.lock().map_err(|e| self.io_error(e))?;
+        Ok(file)
+    }
+
+    pub fn append(&self, jobs: &[Flink], at: i64) -> Result<(), StatusLogError> {
+        let mut lines = String::new();
+        for job in jobs {
+            let record = StatusRecord {
+                at,
+                job: job.clone(),
+            };
+            let line = serde_json::to_string(&record).map_err(|source| StatusLogError::Json {
+                path: self.path.clone(),
+                line: 0,
+                source,
+            })?;
+            lines.push_str(&line);
+            lines.push('\n');
+        }
+        // 一次写入整批记录，减少进程被中断时留下半行的可能
+        let _lock = self.lock()?;
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(lines.as_bytes()))
+            .map_err(|e| self.io_error(e))
+    }
+
+    /// 读取全部记录，文件不存在时返回空；最后一行不完整（写入被中断）时忽略该行
+    pub fn records(&self) -> Result<Vec<StatusRecord>, StatusLogError> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(self.io_error(e)),
+        };
+        let lines: Vec<&str> = content.lines().collect();
+        let mut records = Vec::with_capacity(lines.len());
+        for (i, line) in lines.iter().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(record) => records.push(record),
+                Err(_) if i + 1 == lines.len() && !content.ends_with('\n') => {}
+                Err(source) => {
+                    return Err(StatusLogError::Json {
+                        path: self.path.clone(),
+                        line: i + 1,
+                        source,
+                    });
+                }
+            }
+        }
+        Ok(records)
+    }
+
+    /// 去掉重复记录，并删除 `retain_after`（毫秒时间戳）之前的数据后重写文件；
+    /// 读取到替换文件期间持有锁，其它进程的 `append` 会等待，不会写入被替换掉的旧文件
+    pub fn compact(&self, retain_after: Option<i64>) -> Result<CompactStats, StatusLogError> {
+        let _lock = self.lock()?;
+        let records = self.records()?;
+        let before = records.len();
+        let compacted = compact_records(records, retain_after);
+        let after = compacted.len();
+
+        let mut content = String::new();
+        for record in &compacted {
+            let line = serde_json::to_string(record).map_err(|source| StatusLogError::Json {
+                path: self.path.clone(),
+                line: 0,
+                source,
+            })?;
+            content.push_str(&line);
+            content.push('\n');
+        }
+        let tmp = format!("{}.tmp", self.path);
+        fs::write(&tmp, content)
+            .and_then(|_| fs::rename(&tmp, &self.path))
+            .map_err(|e| self.io_error(e))?;
+        Ok(CompactStats { before, after })
+    }
+
+    /// 查询 `job`（作业名称或 jid）在 `[from, to]` 之间的状态变化
+    pub fn query(&self, job: &str, from: i64, to: i64) -> Result<Vec<StateSpan>, StatusLogError> {
+        let records: Vec<StatusRecord> = self
+            .records()?
+            .into_iter()
+            .filter(|r| r.job.jid == job || r.job.name == job)
+            .collect();
+        Ok(spans(&records, from, to))
+    }
+}
+
+/// 每个 jid 只保留状态发生变化的记录和最后一条记录
+///
+/// 超过保留期的记录被删除，但保留期开始前的最后一条记录会留下来，以便知道保留期开始时作业的状态；
+/// 保留期内没有任何记录的作业只剩这一条。
+pub fn compact_records(
+    mut records: Vec<StatusRecord>,
+    retain_after: Option<i64>,
+) -> Vec<StatusRecord> {
+    records.sort_by_key(|r| r.at);
+    let mut by_job: BTreeMap<String, Vec<StatusRecord>> = BTreeMap::new();
+    for record in records {
+        by_job
+            .entry(record.job.jid.clone())
+            .or_default()
+            .push(record);
+    }
+
+    let mut result = Vec::new();
+    for (_, job_records) in by_job {
+        let mut kept: Vec<StatusRecord> = Vec::new();
+        let last = job_records.len() - 1;
+        for (i, record) in job_records.into_iter().enumerate() {
+            let changed = kept.last().is_none_or(|prev| !prev.same_status(&record));
+            if changed || i == last {
+                kept.push(record);
+            }
+        }
+        // 保留期内没有记录的作业也留下最后一条，仍然知道它当前的状态
+        if let Some(cutoff) = retain_after {
+            let first_retained = kept.partition_point(|r| r.at < cutoff);
+            kept.drain(..first_retained.saturating_sub(1));
+        }
+        result.extend(kept);
+    }
+    result.sort_by_key(|r| r.at);
+    result
+}
+
+/// 把记录合并成状态区间，并截取到 `[from, to]`
+pub fn spans(records: &[StatusRecord], from: i64, to: i64) -> Vec<StateSpan> {
+    let mut by_job: BTreeMap<&str, Vec<&StatusRecord>> = BTreeMap::new();
+    for record in records {
+        by_job.entry(&record.job.jid).or_default().push(record);
+    }
+
+    let mut result = Vec::new();
+    for (_, mut job_records) in by_job {
+        job_records.sort_by_key(|r| r.at);
+        let mut current: Option<StateSpan> = None;
+        for record in job_records {
+            let state = record.job.job_state();
+            match current.as_mut() {
+                Some(span) if span.state == state => span.to = record.at,
+                _ => {
+                    if let Some(mut span) = current.take() {
+                        // 状态在两次轮询之间发生变化，前一个状态持续到新状态第一次出现
+                        span.to = record.at;
+                        result.push(span);
+                    }
+                    current = Some(StateSpan {
+                        jid: record.job.jid.clone(),
+                        name: record.job.name.clone(),
+                        state,
+                        from: record.at,
+                        to: record.at,
+                    });
+                }
+            }
+        }
+        result.extend(current);
+    }
+
+    result.retain(|span| span.to >= from && span.from <= to);
+    for span in &mut result {
+        span.from = span.from.max(from);
+        span.to = span.to.min(to);
+    }
+    result.sort_by_key(|span| span.from);
+    result
+}
+
+fn format_time(ms: i64) -> String {
+    DateTime::from_timestamp_millis(ms)
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| ms.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::flink::TaskInfo;
+    use crate::naming::JobMetadata;
+
+    fn record(at: i64, jid: &str, state: &str) -> StatusRecord {
+        StatusRecord {
+            at,
+            job: Flink {
+                jid: jid.to_string(),
+                name: "orders".to_string(),
+                state: state.to_string(),
+                start_time: Some(0),
+                end_time: None,
+                duration: None,
+                last_modification: None,
+                tasks: TaskInfo::default(),
+                metadata: JobMetadata::default(),
+            },
+        }
+    }
+
+    fn states(records: &[StatusRecord]) -> Vec<(i64, &str)> {
+        records
+            .iter()
+            .map(|r| (r.at, r.job.state.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_compact_records() {
+        let records = vec![
+            record(1, "a", "RUNNING"),
+            record(2, "a", "RUNNING"),
+            record(3, "a", "RUNNING"),
+            record(4, "a", "RESTARTING"),
+            record(5, "a", "RUNNING"),
+            record(6, "a", "RUNNING"),
+            record(2, "old", "CANCELED"),
+        ];
+        let compacted = compact_records(records.clone(), None);
+        assert_eq!(
+            states(&compacted),
+            vec![
+                (1, "RUNNING"),
+                (2, "CANCELED"),
+                (4, "RESTARTING"),
+                (5, "RUNNING"),
+                (6, "RUNNING")
+            ]
+        );
+
+        // 保留期从 3 开始：a 保留 3 之前的最后一条，old 只剩最后一条
+        let compacted = compact_records(records, Some(3));
+        assert_eq!(
+            states(&compacted),
+            vec![
+                (1, "RUNNING"),
+                (2, "CANCELED"),
+                (4, "RESTARTING"),
+                (5, "RUNNING"),
+                (6, "RUNNING")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spans() {
+        let records = vec![
+            record(10, "a", "RUNNING"),
+            record(20, "a", "RUNNING"),
+            record(30, "a", "RESTARTING"),
+            record(40, "a", "FAILED"),
+            record(50, "b", "RUNNING"),
+            record(60, "b", "RUNNING"),
+        ];
+        let result: Vec<(String, JobState, i64, i64)> = spans(&records, 15, 55)
+            .into_iter()
+            .map(|s| (s.jid, s.state, s.from, s.to))
+            .collect();
+        assert_eq!(
+            result,
+            vec![
+                ("a".to_string(), JobState::Running, 15, 30),
+                ("a".to_string(), JobState::Restarting, 30, 40),
+                ("a".to_string(), JobState::Failed, 40, 40),
+                ("b".to_string(), JobState::Running, 50, 55),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compact_keeps_concurrent_appends() {
+        let dir =
+            std::env::temp_dir().join(format!("rust_project_status_log_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("status.jsonl");
+        let log = StatusLog::new(path.to_str().unwrap());
+        let appender = {
+            let log = StatusLog::new(log.path());
+            std::thread::spawn(move || {
+                for at in 0..200 {
+                    let job = record(at, &format!("job-{}", at), "RUNNING").job;
+                    log.append(&[job], at).unwrap();
+                }
+            })
+        };
+        for _ in 0..50 {
+            log.compact(None).unwrap();
+        }
+        appender.join().unwrap();
+        let count = log.records().unwrap().len();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(count, 200);
+    }
+}
@@ -0,0 +1,520 @@
+use crate::client::error::FlinkError;
+use crate::client::flink::FlinkClient;
+use crate::models::enums::JobState;
+use crate::models::flink::Flink;
+use crate::naming::{JobFilter, JobMetadata, NamingConvention};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::fs;
+use std::process::Command;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum StormError {
+    #[error(transparent)]
+    Flink(#[from] FlinkError),
+    #[error("failed to run `{command}`: {source}")]
+    Command {
+        command: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("`{command}` exited with {status}")]
+    CommandFailed { command: String, status: String },
+    #[error("failed to access {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("invalid storm state {path}: {source}")]
+    Json {
+        path: String,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+/// 重启风暴检测配置，对应 config.toml 中的 `[flink.storm]`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StormConfig {
+    /// 窗口内进入 RESTARTING 的次数超过该值时认为发生了重启风暴
+    #[serde(default = "default_max_restarts")]
+    pub max_restarts: usize,
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+    /// 同一作业两次处理之间的最小间隔
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+    /// 按顺序使用第一个匹配的规则，没有匹配时只报告不处理
+    #[serde(default)]
+    pub rules: Vec<StormRule>,
+}
+
+fn default_max_restarts() -> usize {
+    3
+}
+
+fn default_window_secs() -> u64 {
+    600
+}
+
+fn default_cooldown_secs() -> u64 {
+    1800
+}
+
+impl Default for StormConfig {
+    fn default() -> Self {
+        StormConfig {
+            max_restarts: default_max_restarts(),
+            window_secs: default_window_secs(),
+            cooldown_secs: default_cooldown_secs(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StormRule {
+    /// [`JobFilter`] 表达式，例如 `env=prod team=orders`，为空时匹配所有作业
+    #[serde(default)]
+    pub filter: String,
+    #[serde(flatten)]
+    pub remediation: Remediation,
+}
+
+/// 发生重启风暴时的处理方式
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Remediation {
+    None,
+    StopWithSavepoint {
+        #[serde(default)]
+        target_directory: Option<String>,
+    },
+    Cancel,
+    /// 通过 `sh -c` 执行，作业信息通过环境变量 `FLINK_CLUSTER`、`FLINK_JOB_ID`、
+    /// `FLINK_JOB_NAME`、`FLINK_RESTARTS` 传入
+    Command {
+        command: String,
+    },
+}
+
+impl fmt::Display for Remediation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Remediation::None => f.write_str("none"),
+            Remediation::StopWithSavepoint { .. } => f.write_str("stop-with-savepoint"),
+            Remediation::Cancel => f.write_str("cancel"),
+            Remediation::Command { command } => write!(f, "command `{}`", command),
+        }
+    }
+}
+
+/// 在窗口内重启次数超过阈值的作业
+#[derive(Debug, Clone, PartialEq)]
+pub struct Storm {
+    pub jid: String,
+    pub name: String,
+    pub restarts: usize,
+    /// 作业上已解析的命名元数据，用于匹配处理规则
+    pub metadata: JobMetadata,
+}
+
+/// 根据连续的 `/jobs/overview` 轮询结果统计每个作业进入 RESTARTING 的时间
+#[derive(Debug, Default)]
+pub struct StormDetector {
+    max_restarts: usize,
+    window_ms: i64,
+    last_state: BTreeMap<String, JobState>,
+    restarts: BTreeMap<String, VecDeque<i64>>,
+}
+
+impl StormDetector {
+    pub fn new(config: &StormConfig) -> StormDetector {
+        StormDetector {
+            max_restarts: config.max_restarts,
+            window_ms: config.window_secs as i64 * 1000,
+            ..StormDetector::default()
+        }
+    }
+
+    /// 记录一次轮询，返回当前处于重启风暴中的作业
+    pub fn observe(&mut self, jobs: &[Flink], now: i64) -> Vec<Storm> {
+        let mut storms = Vec::new();
+        for job in jobs {
+            let state = job.job_state();
+            let previous = self.last_state.insert(job.jid.clone(), state);
+            let restarts = self.restarts.entry(job.jid.clone()).or_default();
+            if state == JobState::Restarting && previous != Some(JobState::Restarting) {
+                restarts.push_back(now);
+            }
+            while restarts.front().is_some_and(|t| now - t > self.window_ms) {
+                restarts.pop_front();
+            }
+            if restarts.len() > self.max_restarts && !state.is_terminal() {
+                storms.push(Storm {
+                    jid: job.jid.clone(),
+                    name: job.name.clone(),
+                    restarts: restarts.len(),
+                    metadata: job.metadata.clone(),
+                });
+            }
+        }
+        // 已经从列表中消失的作业不再跟踪
+        self.last_state
+            .retain(|jid, _| jobs.iter().any(|job| &job.jid == jid));
+        let last_state = &self.last_state;
+        self.restarts.retain(|jid, _| last_state.contains_key(jid));
+        storms
+    }
+}
+
+#[derive(Debug)]
+pub enum Outcome {
+    /// 没有匹配的规则或规则为 `none`，只报告
+    Reported,
+    DryRun,
+    /// 冷却期内不重复处理，值为冷却结束时间
+    CoolingDown(i64),
+    Done(String),
+    Failed(StormError),
+}
+
+#[derive(Debug)]
+pub struct StormEvent {
+    pub storm: Storm,
+    pub remediation: Remediation,
+    pub outcome: Outcome,
+}
+
+impl fmt::Display for StormEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}) restarted {} times: ",
+            self.storm.name, self.storm.jid, self.storm.restarts
+        )?;
+        match &self.outcome {
+            Outcome::Reported => f.write_str("no remediation configured"),
+            Outcome::DryRun => write!(f, "would run {} (dry-run)", self.remediation),
+            Outcome::CoolingDown(until) => {
+                let until = chrono::DateTime::from_timestamp_millis(*until)
+                    .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+                    .unwrap_or_else(|| until.to_string());
+                write!(
+                    f,
+                    "{} skipped, cooling down until {}",
+                    self.remediation, until
+                )
+            }
+            Outcome::Done(message) => write!(f, "{}: {}", self.remediation, message),
+            Outcome::Failed(e) => write!(f, "{} failed: {}", self.remediation, e),
+        }
+    }
+}
+
+/// 两次运行之间需要保留的检测状态：每个作业上次的状态、窗口内的重启时间和上次处理的时间，
+/// `flink_cli storm --once --state file` 由 cron 调用时靠它跨进程累计重启次数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StormState {
+    #[serde(default)]
+    pub last_state: BTreeMap<String, JobState>,
+    #[serde(default)]
+    pub restarts: BTreeMap<String, VecDeque<i64>>,
+    #[serde(default)]
+    pub last_action: BTreeMap<String, i64>,
+}
+
+impl StormState {
+    /// 文件不存在时返回空的状态
+    pub fn load(path: &str) -> Result<StormState, StormError> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(StormState::default());
+            }
+            Err(source) => {
+                return Err(StormError::Io {
+                    path: path.to_string(),
+                    source,
+                });
+            }
+        };
+        serde_json::from_str(&content).map_err(|source| StormError::Json {
+            path: path.to_string(),
+            source,
+        })
+    }
+
+    /// 先写临时文件再重命名，避免中断时留下不完整的文件
+    pub fn save(&self, path: &str) -> Result<(), StormError> {
+        let json = serde_json::to_string_pretty(self).map_err(|source| StormError::Json {
+            path: path.to_string(),
+            source,
+        })?;
+        let tmp = format!("{}.tmp", path);
+        fs::write(&tmp, json)
+            .and_then(|_| fs::rename(&tmp, path))
+            .map_err(|source| StormError::Io {
+                path: path.to_string(),
+                source,
+            })
+    }
+}
+
+/// 检测重启风暴并按规则处理，同一作业（按名称）在冷却期内只处理一次
+pub struct StormMonitor {
+    config: StormConfig,
+    naming: NamingConvention,
+    filters: Vec<JobFilter>,
+    detector: StormDetector,
+    dry_run: bool,
+    last_action: BTreeMap<String, i64>,
+}
+
+impl StormMonitor {
+    pub fn new(config: StormConfig, naming: NamingConvention, dry_run: bool) -> StormMonitor {
+        StormMonitor {
+            filters: config
+                .rules
+                .iter()
+                .map(|rule| JobFilter::parse(&rule.filter))
+                .collect(),
+            detector: StormDetector::new(&config),
+            config,
+            naming,
+            dry_run,
+            last_action: BTreeMap::new(),
+        }
+    }
+
+    /// 恢复上次运行保存的状态
+    pub fn restore(&mut self, state: StormState) {
+        self.detector.last_state = state.last_state;
+        self.detector.restarts = state.restarts;
+        self.last_action = state.last_action;
+    }
+
+    pub fn state(&self) -> StormState {
+        StormState {
+            last_state: self.detector.last_state.clone(),
+            restarts: self.detector.restarts.clone(),
+            last_action: self.last_action.clone(),
+        }
+    }
+
+    fn remediation_for(&self, storm: &Storm) -> Remediation {
+        self.filters
+            .iter()
+            .position(|filter| filter.matches(&storm.name, &storm.metadata))
+            .map_or(Remediation::None, |i| {
+                self.config.rules[i].remediation.clone()
+            })
+    }
+
+    pub fn poll(&mut self, client: &FlinkClient, now: i64) -> Result<Vec<StormEvent>, FlinkError> {
+        let mut jobs = client.jobs_overview()?;
+        self.naming.annotate(&mut jobs);
+        Ok(self.handle(client, &jobs, now))
+    }
+
+    /// `jobs` 需要已经用 [`NamingConvention::annotate`] 解析过元数据，[`StormMonitor::poll`] 会自动完成
+    pub fn handle(&mut self, client: &FlinkClient, jobs: &[Flink], now: i64) -> Vec<StormEvent> {
+        let cooldown_ms = self.config.cooldown_secs as i64 * 1000;
+        let mut events = Vec::new();
+        for storm in self.detector.observe(jobs, now) {
+            let remediation = self.remediation_for(&storm);
+            let outcome = if remediation == Remediation::None {
+                Outcome::Reported
+            } else if let Some(last) = self.last_action.get(&storm.name)
+                && now - last < cooldown_ms
+            {
+                Outcome::CoolingDown(last + cooldown_ms)
+            } else {
+                // dry-run 也记录冷却时间，避免每次轮询都重复输出
+                self.last_action.insert(storm.name.clone(), now);
+                if self.dry_run {
+                    Outcome::DryRun
+                } else {
+                    match remediate(client, &storm, &remediation) {
+                        Ok(message) => Outcome::Done(message),
+                        Err(e) => Outcome::Failed(e),
+                    }
+                }
+            };
+            events.push(StormEvent {
+                storm,
+                remediation,
+                outcome,
+            });
+        }
+        events
+    }
+}
+
+pub fn remediate(
+    client: &FlinkClient,
+    storm: &Storm,
+    remediation: &Remediation,
+) -> Result<String, StormError> {
+    match remediation {
+        Remediation::None => Ok(String::from("nothing to do")),
+        Remediation::StopWithSavepoint { target_directory } => {
+            let trigger =
+                client.stop_with_savepoint(&storm.jid, target_directory.as_deref(), false)?;
+            Ok(format!("savepoint triggered ({})", trigger))
+        }
+        Remediation::Cancel => {
+            client.cancel_job(&storm.jid)?;
+            Ok(String::from("cancel requested"))
+        }
+        Remediation::Command { command } => {
+            let status = Command::new("sh")
+                .arg("-c")
+                .arg(command)
+                .env("FLINK_CLUSTER", &client.cluster().name)
+                .env("FLINK_JOB_ID", &storm.jid)
+                .env("FLINK_JOB_NAME", &storm.name)
+                .env("FLINK_RESTARTS", storm.restarts.to_string())
+                .status()
+                .map_err(|source| StormError::Command {
+                    command: command.clone(),
+                    source,
+                })?;
+            if !status.success() {
+                return Err(StormError::CommandFailed {
+                    command: command.clone(),
+                    status: status.to_string(),
+                });
+            }
+            Ok(String::from("command finished"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::flink::ClusterConfig;
+    use crate::models::flink::TaskInfo;
+
+    const MINUTE: i64 = 60 * 1000;
+
+    fn job(name: &str, state: &str) -> Flink {
+        Flink {
+            jid: format!("{}-1", name),
+            name: name.to_string(),
+            state: state.to_string(),
+            start_time: Some(0),
+            end_time: None,
+            duration: None,
+            last_modification: None,
+            tasks: TaskInfo::default(),
+            metadata: JobMetadata::default(),
+        }
+    }
+
+    fn config() -> StormConfig {
+        toml::from_str(
+            r#"
+            max_restarts = 2
+            window_secs = 600
+            cooldown_secs = 1800
+
+            [[rules]]
+            filter = "orders"
+            action = "stop_with_savepoint"
+            target_directory = "s3://savepoints"
+            "#,
+        )
+        .unwrap()
+    }
+
+    /// 在 `start` 之后每分钟交替轮询一次 RUNNING / RESTARTING
+    fn flap(
+        monitor: &mut StormMonitor,
+        client: &FlinkClient,
+        name: &str,
+        start: i64,
+        times: i64,
+    ) -> Vec<StormEvent> {
+        let mut events = Vec::new();
+        for i in 0..times * 2 {
+            let state = if i % 2 == 0 { "RUNNING" } else { "RESTARTING" };
+            events.extend(monitor.handle(client, &[job(name, state)], start + i * MINUTE));
+        }
+        events
+    }
+
+    #[test]
+    fn test_detector_window() {
+        let mut detector = StormDetector::new(&config());
+        let mut storms = Vec::new();
+        for (i, state) in [
+            "RUNNING",
+            "RESTARTING",
+            "RESTARTING",
+            "RUNNING",
+            "RESTARTING",
+        ]
+        .iter()
+        .enumerate()
+        {
+            storms = detector.observe(&[job("orders", state)], i as i64 * MINUTE);
+        }
+        // 连续两次 RESTARTING 只算一次重启
+        assert!(storms.is_empty());
+
+        let storms = detector.observe(&[job("orders", "RESTARTING")], 6 * MINUTE);
+        assert!(storms.is_empty());
+        detector.observe(&[job("orders", "RUNNING")], 7 * MINUTE);
+        let storms = detector.observe(&[job("orders", "RESTARTING")], 8 * MINUTE);
+        assert_eq!(storms[0].restarts, 3);
+
+        // 窗口滑过之后最早的重启不再计数
+        detector.observe(&[job("orders", "RUNNING")], 13 * MINUTE);
+        assert!(
+            detector
+                .observe(&[job("orders", "RUNNING")], 14 * MINUTE)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_dry_run_and_cooldown() {
+        let cluster: ClusterConfig = toml::from_str(
+            r#"
+            name = "test"
+            url = "http://localhost:1"
+            "#,
+        )
+        .unwrap();
+        let client = FlinkClient::new(cluster).unwrap();
+        let mut monitor = StormMonitor::new(config(), NamingConvention::default(), true);
+
+        let events = flap(&mut monitor, &client, "orders", 0, 4);
+        assert!(matches!(events[0].outcome, Outcome::DryRun));
+        assert!(
+            matches!(events[1].outcome, Outcome::CoolingDown(until) if until == 30 * MINUTE + 5 * MINUTE)
+        );
+        assert_eq!(
+            events[0].remediation,
+            Remediation::StopWithSavepoint {
+                target_directory: Some("s3://savepoints".to_string())
+            }
+        );
+
+        // 恢复保存的状态后，新进程仍在冷却期内
+        let state: StormState =
+            serde_json::from_str(&serde_json::to_string(&monitor.state()).unwrap()).unwrap();
+        let mut restored = StormMonitor::new(config(), NamingConvention::default(), true);
+        restored.restore(state);
+        let events = restored.handle(&client, &[job("orders", "RESTARTING")], 10 * MINUTE);
+        assert!(matches!(events[0].outcome, Outcome::CoolingDown(_)));
+
+        let events = flap(&mut monitor, &client, "payments", 0, 3);
+        assert!(matches!(events[0].outcome, Outcome::Reported));
+    }
+}